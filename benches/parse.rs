@@ -0,0 +1,82 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+struct CountingAlloc;
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+fn count_allocations(f: impl FnOnce()) -> usize {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    f();
+    ALLOCATIONS.load(Ordering::Relaxed) - before
+}
+
+fn transient_fixture(no_of_points: usize) -> String {
+    let mut file = format!(
+        "Title: rc circuit
+Date: Sat Dec 25 20:36:08  2021
+Plotname: Transient Analysis
+Flags: real
+No. Variables: 3
+No. Points: {}
+Variables:
+\t0\ttime\ttime
+\t1\tv(a)\tvoltage
+\t2\ti(v1)\tcurrent
+Values:
+",
+        no_of_points
+    );
+    for i in 0..no_of_points {
+        let time = i as f64 * 1e-9;
+        file += format!(
+            " {}\t{:.15e}\n\t{:.15e}\n\t{:.15e}\n\n",
+            i,
+            time,
+            (time * 1e6).sin(),
+            -(time * 1e6).cos() * 1e-3
+        )
+        .as_str();
+    }
+    file
+}
+
+fn main() {
+    let file = transient_fixture(1_000_000);
+    let start = Instant::now();
+    let plot = ngspice_parser::parse(&file).unwrap();
+    println!(
+        "parsed {} points x {} variables in {:?}",
+        plot.no_of_points,
+        plot.no_of_variables,
+        start.elapsed()
+    );
+
+    let small = transient_fixture(10);
+    let owned = count_allocations(|| {
+        let plots: Vec<_> = (0..1000)
+            .map(|_| ngspice_parser::parse(&small).unwrap())
+            .collect();
+        drop(plots);
+    });
+    let mut interner = ngspice_parser::Interner::new();
+    let opts = ngspice_parser::ParseOptions::default();
+    let interned = count_allocations(|| {
+        let plots: Vec<_> = (0..1000)
+            .map(|_| ngspice_parser::parse_with_interner(&small, opts, &mut interner).unwrap())
+            .collect();
+        drop(plots);
+    });
+    println!("1000 plots: {owned} allocations owned, {interned} interned");
+}
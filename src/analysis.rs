@@ -0,0 +1,378 @@
+use crate::export::rectangular;
+use crate::{math, Flags, Plot, SpiceParseError, VarData};
+use alloc::format;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::f64::consts::PI;
+
+const SCALE_TOLERANCE: f64 = 1e-9;
+
+type Bracket = (usize, usize, f64);
+
+/// Positions of the first minimum and maximum of `column`, skipping NaNs.
+fn min_max_positions(column: &[f64]) -> Option<(usize, usize)> {
+    let mut ret: Option<(usize, usize)> = None;
+    for (idx, &y) in column.iter().enumerate() {
+        if y.is_nan() {
+            continue;
+        }
+        ret = match ret {
+            Option::None => Some((idx, idx)),
+            Option::Some((lo, hi)) => Some((
+                if y < column[lo] { idx } else { lo },
+                if y > column[hi] { idx } else { hi },
+            )),
+        };
+    }
+    ret
+}
+fn bracket(scale: &[f64], x: f64) -> Option<Bracket> {
+    if !(*scale.first()?..=*scale.last()?).contains(&x) {
+        return None;
+    }
+    let idx = scale.partition_point(|&s| s < x);
+    if scale[idx] == x {
+        return Some((idx, idx, 0.0));
+    }
+    let (x0, x1) = (scale[idx - 1], scale[idx]);
+    Some((idx - 1, idx, (x - x0) / (x1 - x0)))
+}
+
+fn same_scale(a: &[f64], b: &[f64]) -> bool {
+    a.len() == b.len()
+        && a.iter()
+            .zip(b.iter())
+            .all(|(&x, &y)| (x - y).abs() <= SCALE_TOLERANCE * x.abs().max(y.abs()))
+}
+
+fn lerp(column: &[f64], (i0, i1, t): Bracket) -> Option<f64> {
+    let y0 = *column.get(i0)?;
+    if i0 == i1 {
+        return Some(y0);
+    }
+    let y1 = *column.get(i1)?;
+    Some(y0 + (y1 - y0) * t)
+}
+
+impl VarData {
+    /// Returns `20 * log10(magnitude)` for complex variables. A zero magnitude
+    /// gives negative infinity.
+    pub fn magnitude_db(&self) -> Option<Vec<f64>> {
+        if !self.is_complex() {
+            return None;
+        }
+        Some(
+            self.values
+                .iter()
+                .map(|&val| 20.0 * math::log10(val))
+                .collect(),
+        )
+    }
+    /// Removes the 2π jumps `atan2` introduces wherever consecutive phase
+    /// samples differ by more than π.
+    pub fn phase_unwrapped(&self) -> Option<Vec<f64>> {
+        let angles = self.phases()?;
+        let mut ret: Vec<f64> = Vec::with_capacity(angles.len());
+        let mut offset: f64 = 0.0;
+        for (idx, &angle) in angles.iter().enumerate() {
+            if idx > 0 {
+                let delta = angle - angles[idx - 1];
+                if delta > PI {
+                    offset -= 2.0 * PI;
+                } else if delta < -PI {
+                    offset += 2.0 * PI;
+                }
+            }
+            ret.push(angle + offset);
+        }
+        Some(ret)
+    }
+    pub fn min(&self) -> Option<f64> {
+        self.values.iter().copied().reduce(f64::min)
+    }
+    pub fn max(&self) -> Option<f64> {
+        self.values.iter().copied().reduce(f64::max)
+    }
+    pub fn mean(&self) -> Option<f64> {
+        if self.values.is_empty() {
+            return None;
+        }
+        Some(self.values.iter().sum::<f64>() / self.values.len() as f64)
+    }
+    pub fn rms(&self) -> Option<f64> {
+        if self.values.is_empty() {
+            return None;
+        }
+        let sum_of_squares: f64 = self.values.iter().map(|val| val * val).sum();
+        Some(math::sqrt(sum_of_squares / self.values.len() as f64))
+    }
+}
+
+impl Plot {
+    fn map_columns<F: Fn(&[f64]) -> Vec<f64>>(&self, point_indices: Vec<usize>, f: F) -> Plot {
+        let data: Vec<VarData> = self
+            .data
+            .iter()
+            .map(|var_data| VarData {
+                name: var_data.name.clone(),
+                typee: var_data.typee.clone(),
+                extra: var_data.extra.clone(),
+                values: f(&var_data.values),
+                angles: var_data.angles.as_deref().map(&f),
+                real: var_data.real.as_deref().map(&f),
+                imag: var_data.imag.as_deref().map(&f),
+            })
+            .collect();
+        Plot {
+            title: self.title.clone(),
+            date: self.date.clone(),
+            plotname: self.plotname.clone(),
+            flags: self.flags,
+            flag_modifiers: self.flag_modifiers.clone(),
+            no_of_variables: self.no_of_variables,
+            no_of_points: data.first().map_or(0, |var_data| var_data.values.len()),
+            dimensions: Option::None,
+            command: self.command.clone(),
+            extra_headers: self.extra_headers.clone(),
+            point_indices,
+            data,
+        }
+    }
+    /// Linearly interpolates `values` of `var` at scale value `x`. The scale is
+    /// assumed to be monotonically increasing, as ngspice writes it for
+    /// `.tran`, `.ac` and `.dc` sweeps.
+    pub fn interpolate(&self, var: &str, x: f64) -> Option<f64> {
+        let scale = &self.data.get(self.scale_index())?.values;
+        lerp(&self.variable(var)?.values, bracket(scale, x)?)
+    }
+    /// Returns `(scale_value, value)` of the sample whose scale value is
+    /// closest to `x`, found by binary search on a monotonically increasing
+    /// scale. Ties go to the earlier sample.
+    pub fn sample_at(&self, var: &str, x: f64) -> Option<(f64, f64)> {
+        let scale = &self.data.get(self.scale_index())?.values;
+        let values = &self.variable(var)?.values;
+        let n = scale.len().min(values.len());
+        if n == 0 || x.is_nan() {
+            return None;
+        }
+        let idx = scale[..n].partition_point(|&s| s < x);
+        let idx = match (idx.checked_sub(1), idx < n) {
+            (Some(lo), true) if x - scale[lo] <= scale[idx] - x => lo,
+            (Some(lo), false) => lo,
+            _ => idx,
+        };
+        Some((scale[idx], values[idx]))
+    }
+    fn extremum(&self, var: &str, better: fn(f64, f64) -> bool) -> Option<(f64, f64)> {
+        let scale = &self.data.get(self.scale_index())?.values;
+        let values = &self.variable(var)?.values;
+        let mut best: Option<(f64, f64)> = None;
+        for (&x, &y) in scale.iter().zip(values.iter()) {
+            if !y.is_nan() && best.is_none_or(|(_, best_y)| better(y, best_y)) {
+                best = Some((x, y));
+            }
+        }
+        best
+    }
+    /// Returns `(scale_value, value)` at the first maximum of `var`.
+    pub fn argmax(&self, var: &str) -> Option<(f64, f64)> {
+        self.extremum(var, |y, best| y > best)
+    }
+    /// Returns `(scale_value, value)` at the first minimum of `var`.
+    pub fn argmin(&self, var: &str) -> Option<(f64, f64)> {
+        self.extremum(var, |y, best| y < best)
+    }
+    /// Returns `-dφ/dω` of `var` with `ω = 2π·frequency`, using the unwrapped
+    /// phase. The result has one value per point: central differences inside
+    /// the sweep and one-sided differences at both ends. Needs a complex plot
+    /// with at least two points.
+    pub fn group_delay(&self, var: &str) -> Option<Vec<f64>> {
+        let scale = &self.data.get(self.scale_index())?.values;
+        let phase = self.variable(var)?.phase_unwrapped()?;
+        let n = phase.len().min(scale.len());
+        if n < 2 {
+            return None;
+        }
+        let omega: Vec<f64> = scale.iter().map(|&freq| 2.0 * PI * freq).collect();
+        let ret: Vec<f64> = (0..n)
+            .map(|idx| {
+                let (lo, hi) = (idx.saturating_sub(1), (idx + 1).min(n - 1));
+                -(phase[hi] - phase[lo]) / (omega[hi] - omega[lo])
+            })
+            .collect();
+        Some(ret)
+    }
+    /// Divides `numerator` by `denominator` point by point, such as
+    /// `v(out)/v(in)` for a transfer function. Complex plots divide in
+    /// rectangular form and yield a complex variable; points where the
+    /// denominator is zero come out as NaN in both parts. Real plots follow
+    /// IEEE division, giving ±inf or NaN there. The result is named
+    /// `{numerator}/{denominator}` and typed `notype`.
+    pub fn ratio(&self, numerator: &str, denominator: &str) -> Option<VarData> {
+        let (num, den) = (self.variable(numerator)?, self.variable(denominator)?);
+        let n = num.values.len().min(den.values.len());
+        let name = format!("{}/{}", numerator, denominator);
+        if let Flags::Real = self.flags {
+            let values = (0..n)
+                .map(|idx| num.values[idx] / den.values[idx])
+                .collect();
+            return Some(VarData::new_real(&name, "notype", values));
+        }
+        let (real, imag): (Vec<f64>, Vec<f64>) = (0..n)
+            .map(|idx| {
+                let ((a, b), (c, d)) = (rectangular(num, idx), rectangular(den, idx));
+                let norm = c * c + d * d;
+                ((a * c + b * d) / norm, (b * c - a * d) / norm)
+            })
+            .unzip();
+        VarData::new_complex(&name, "notype", real, imag).ok()
+    }
+    /// Keeps every `stride`-th point, starting with the first one. A stride
+    /// of zero is treated as one.
+    pub fn decimate(&self, stride: usize) -> Plot {
+        let stride = stride.max(1);
+        let point_indices = self.point_indices.iter().step_by(stride).copied().collect();
+        self.map_columns(point_indices, |column| {
+            column.iter().step_by(stride).copied().collect()
+        })
+    }
+    /// Downsamples to about `target_points` points without hiding spikes. The
+    /// scale range is split into `target_points / 2` equal buckets, assuming a
+    /// monotonic scale, and each bucket keeps the samples holding the minimum
+    /// and the maximum of every variable. With many variables a bucket may
+    /// thus keep more than two points. Plots that already fit are kept whole.
+    pub fn decimate_minmax(&self, target_points: usize) -> Plot {
+        let points = self.actual_points();
+        let scale: &[f64] = self
+            .data
+            .get(self.scale_index())
+            .map_or(&[], |var_data| &var_data.values[..points]);
+        if points <= target_points.max(2) {
+            return self.clone();
+        }
+        let buckets = (target_points / 2).max(1);
+        let (first, last) = (scale[0], scale[points - 1]);
+        let mut keep: Vec<usize> = Vec::new();
+        let mut start: usize = 0;
+        for bucket in 1..=buckets {
+            let end = match bucket == buckets {
+                true => points,
+                false => {
+                    let edge = first + (last - first) * bucket as f64 / buckets as f64;
+                    scale.partition_point(|&s| s < edge).max(start)
+                }
+            };
+            if end == start {
+                continue;
+            }
+            let mut picks: Vec<usize> = Vec::new();
+            for (var_idx, var_data) in self.data.iter().enumerate() {
+                if var_idx == self.scale_index() {
+                    continue;
+                }
+                if let Some((lo, hi)) = min_max_positions(&var_data.values[start..end]) {
+                    picks.extend([start + lo, start + hi]);
+                }
+            }
+            if picks.is_empty() {
+                picks.push(start);
+            }
+            picks.sort_unstable();
+            picks.dedup();
+            keep.extend(picks);
+            start = end;
+        }
+        let point_indices = keep
+            .iter()
+            .filter_map(|&idx| self.point_indices.get(idx).copied())
+            .collect();
+        self.map_columns(point_indices, |column| {
+            keep.iter().map(|&idx| column[idx]).collect()
+        })
+    }
+    /// Collapses a complex plot into a real one holding the magnitudes, with
+    /// the phase and rectangular columns dropped.
+    pub fn to_magnitude_plot(&self) -> Plot {
+        let mut plot = self.clone();
+        plot.flags = Flags::Real;
+        for var_data in plot.data.iter_mut() {
+            var_data.angles = None;
+            var_data.real = None;
+            var_data.imag = None;
+        }
+        plot
+    }
+    /// Linearly interpolates every column onto `n` evenly spaced scale values
+    /// between the first and last sample, assuming a monotonic scale. The new
+    /// points are numbered from zero.
+    pub fn resample_uniform(&self, n: usize) -> Plot {
+        let scale: &[f64] = self
+            .data
+            .get(self.scale_index())
+            .map_or(&[], |var_data| &var_data.values);
+        let brackets: Vec<Bracket> = match (scale.first(), scale.last()) {
+            (Option::Some(&first), Option::Some(&last)) => (0..n)
+                .map(|i| {
+                    let x = match n {
+                        1 => first,
+                        _ => (first + (last - first) * i as f64 / (n - 1) as f64).min(last),
+                    };
+                    bracket(scale, x).unwrap_or((0, 0, 0.0))
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+        self.map_columns((0..brackets.len()).collect(), |column| {
+            brackets
+                .iter()
+                .map(|&b| lerp(column, b).unwrap_or(f64::NAN))
+                .collect()
+        })
+    }
+    /// Keeps the points whose scale value lies in the inclusive range
+    /// `[lo, hi]`, assuming a monotonic scale. The result is empty when no
+    /// sample falls in the range.
+    pub fn slice_range(&self, lo: f64, hi: f64) -> Plot {
+        let scale: &[f64] = self
+            .data
+            .get(self.scale_index())
+            .map_or(&[], |var_data| &var_data.values);
+        let start = scale.partition_point(|&s| s < lo);
+        let end = scale.partition_point(|&s| s <= hi).max(start);
+        let point_indices = self.point_indices.get(start..end).unwrap_or(&[]).to_vec();
+        self.map_columns(point_indices, |column| {
+            column.get(start..end).unwrap_or(&[]).to_vec()
+        })
+    }
+    /// Combines plots sharing the same scale into one. The scale of the first
+    /// plot is kept and every other variable is renamed to `{index}:{name}`,
+    /// where `index` is the position of its plot in `plots`.
+    pub fn merge_by_scale(plots: &[Plot]) -> Result<Plot, SpiceParseError> {
+        let first = plots.first().ok_or(SpiceParseError::ScaleMismatch)?;
+        let scale = first
+            .data
+            .get(first.scale_index())
+            .ok_or(SpiceParseError::ScaleMismatch)?;
+        let mut data: Vec<VarData> = vec![scale.clone()];
+        for (idx, plot) in plots.iter().enumerate() {
+            let other = plot.data.get(plot.scale_index());
+            if plot.flags != first.flags
+                || !other.is_some_and(|other| same_scale(&other.values, &scale.values))
+            {
+                return Result::Err(SpiceParseError::ScaleMismatch);
+            }
+            for (var_idx, var_data) in plot.data.iter().enumerate() {
+                if var_idx != plot.scale_index() {
+                    let mut var_data = var_data.clone();
+                    var_data.name = format!("{}:{}", idx, var_data.name);
+                    data.push(var_data);
+                }
+            }
+        }
+        Result::Ok(Plot {
+            no_of_variables: data.len(),
+            data,
+            ..first.clone()
+        })
+    }
+}
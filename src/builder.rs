@@ -0,0 +1,103 @@
+use crate::{Flags, Plot, SpiceParseError, VarData};
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+type PendingVariable = (String, String, Vec<f64>, Option<Vec<f64>>);
+
+/// Assembles a [`Plot`] from value vectors. `no_of_points` and
+/// `no_of_variables` are derived from the pushed variables on `build()`.
+/// Variable names are kept as given, so duplicates are allowed.
+#[derive(Debug, Clone)]
+pub struct PlotBuilder {
+    title: String,
+    date: String,
+    plotname: String,
+    flags: Flags,
+    variables: Vec<PendingVariable>,
+}
+impl Default for PlotBuilder {
+    fn default() -> Self {
+        PlotBuilder {
+            title: String::new(),
+            date: String::new(),
+            plotname: String::new(),
+            flags: Flags::Real,
+            variables: Vec::new(),
+        }
+    }
+}
+impl PlotBuilder {
+    pub fn new() -> PlotBuilder {
+        PlotBuilder::default()
+    }
+    pub fn title(mut self, title: &str) -> PlotBuilder {
+        self.title = String::from(title);
+        self
+    }
+    pub fn date(mut self, date: &str) -> PlotBuilder {
+        self.date = String::from(date);
+        self
+    }
+    pub fn plotname(mut self, plotname: &str) -> PlotBuilder {
+        self.plotname = String::from(plotname);
+        self
+    }
+    pub fn flags(mut self, flags: Flags) -> PlotBuilder {
+        self.flags = flags;
+        self
+    }
+    /// Pushes a real variable. In a complex plot its imaginary part is zero.
+    pub fn variable(mut self, name: &str, typee: &str, values: Vec<f64>) -> PlotBuilder {
+        self.variables
+            .push((String::from(name), String::from(typee), values, None));
+        self
+    }
+    pub fn complex_variable(
+        mut self,
+        name: &str,
+        typee: &str,
+        real: Vec<f64>,
+        imag: Vec<f64>,
+    ) -> PlotBuilder {
+        self.variables
+            .push((String::from(name), String::from(typee), real, Some(imag)));
+        self
+    }
+    /// Fails with `NoOfPointMismatch` if the vectors differ in length and
+    /// with `NotComplex` if a complex variable is pushed into a real plot.
+    pub fn build(self) -> Result<Plot, SpiceParseError> {
+        let no_of_points = self.variables.first().map_or(0, |var| var.2.len());
+        let mut data: Vec<VarData> = Vec::with_capacity(self.variables.len());
+        for (name, typee, real, imag) in self.variables {
+            let lengths_match = real.len() == no_of_points
+                && imag.as_ref().is_none_or(|imag| imag.len() == no_of_points);
+            if !lengths_match {
+                return Result::Err(SpiceParseError::NoOfPointMismatch);
+            }
+            let var_data = match (self.flags, imag) {
+                (Flags::Real, Some(_)) => return Result::Err(SpiceParseError::NotComplex),
+                (Flags::Real, None) => VarData::new_real(&name, &typee, real),
+                (Flags::Complex, imag) => {
+                    let imag = imag.unwrap_or_else(|| vec![0f64; no_of_points]);
+                    VarData::new_complex(&name, &typee, real, imag)?
+                }
+            };
+            data.push(var_data);
+        }
+        Result::Ok(Plot {
+            title: self.title,
+            date: self.date,
+            plotname: self.plotname,
+            flags: self.flags,
+            flag_modifiers: Vec::new(),
+            no_of_variables: data.len(),
+            no_of_points,
+            dimensions: None,
+            command: None,
+            extra_headers: Vec::new(),
+            point_indices: (0..no_of_points).collect(),
+            data,
+        })
+    }
+}
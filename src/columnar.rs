@@ -0,0 +1,86 @@
+use crate::{Flags, Plot, Variable};
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// A plot whose values live in one flat column-major buffer: the `points`
+/// values of variable `var` start at `var * points`. Complex plots keep the
+/// magnitudes in `values` and the phases in `angles`, with the same layout.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnarPlot {
+    pub title: String,
+    pub date: String,
+    pub plotname: String,
+    pub flags: Flags,
+    pub points: usize,
+    pub variables: Vec<Variable>,
+    pub values: Vec<f64>,
+    pub angles: Option<Vec<f64>>,
+}
+impl ColumnarPlot {
+    pub fn column(&self, var: usize) -> Option<&[f64]> {
+        let start = var.checked_mul(self.points)?;
+        self.values.get(start..start + self.points)
+    }
+    pub fn angle_column(&self, var: usize) -> Option<&[f64]> {
+        let start = var.checked_mul(self.points)?;
+        self.angles.as_ref()?.get(start..start + self.points)
+    }
+    /// The values of every variable at `point`, or `None` past the end.
+    pub fn row(&self, point: usize) -> Option<Vec<f64>> {
+        if point >= self.points {
+            return None;
+        }
+        Some(
+            (0..self.variables.len())
+                .map(|var| self.values[var * self.points + point])
+                .collect(),
+        )
+    }
+    pub fn get(&self, point: usize, var: usize) -> Option<f64> {
+        self.column(var)?.get(point).copied()
+    }
+    /// Pointer and length of the whole `values` buffer for handing to C. The
+    /// pointer stays valid until the plot is mutated or dropped.
+    pub fn values_raw(&self) -> (*const f64, usize) {
+        (self.values.as_ptr(), self.values.len())
+    }
+    pub fn angles_raw(&self) -> Option<(*const f64, usize)> {
+        let angles = self.angles.as_ref()?;
+        Some((angles.as_ptr(), angles.len()))
+    }
+}
+impl Plot {
+    pub fn into_columnar(self) -> ColumnarPlot {
+        let points = self.actual_points();
+        let mut values: Vec<f64> = Vec::with_capacity(points * self.data.len());
+        let mut angles: Option<Vec<f64>> = match self.flags {
+            Flags::Real => None,
+            Flags::Complex => Some(Vec::with_capacity(points * self.data.len())),
+        };
+        let mut variables: Vec<Variable> = Vec::with_capacity(self.data.len());
+        for var_data in self.data {
+            values.extend_from_slice(&var_data.values[..points]);
+            if let Some(angles) = angles.as_mut() {
+                match var_data.phases() {
+                    Some(column) => angles.extend_from_slice(&column[..points]),
+                    None => angles.extend(core::iter::repeat_n(0f64, points)),
+                }
+            }
+            variables.push(Variable {
+                name: var_data.name,
+                typee: var_data.typee,
+                extra: var_data.extra,
+            });
+        }
+        ColumnarPlot {
+            title: self.title,
+            date: self.date,
+            plotname: self.plotname,
+            flags: self.flags,
+            points,
+            variables,
+            values,
+            angles,
+        }
+    }
+}
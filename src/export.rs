@@ -0,0 +1,198 @@
+use crate::{math, Flags, Plot, SpiceParseError, VarData};
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+fn format_value(value: f64) -> String {
+    let formatted = format!("{:.15e}", value);
+    match formatted.split_once('e') {
+        Some((mantissa, exponent)) => {
+            let (sign, digits) = match exponent.strip_prefix('-') {
+                Some(digits) => ('-', digits),
+                None => ('+', exponent),
+            };
+            format!("{}e{}{:0>2}", mantissa, sign, digits)
+        }
+        None => formatted,
+    }
+}
+pub(crate) fn rectangular(var_data: &VarData, idx: usize) -> (f64, f64) {
+    match (&var_data.real, &var_data.imag, &var_data.angles) {
+        (Some(real), Some(imag), _) => (real[idx], imag[idx]),
+        (_, _, Some(angles)) => {
+            let (magnitude, phase) = (var_data.values[idx], angles[idx]);
+            (magnitude * math::cos(phase), magnitude * math::sin(phase))
+        }
+        _ => (var_data.values[idx], 0f64),
+    }
+}
+impl Plot {
+    fn rawfile_header(&self, points: usize) -> String {
+        let mut ret = String::new();
+        ret += format!("Title: {}\n", self.title).as_str();
+        ret += format!("Date: {}\n", self.date).as_str();
+        ret += format!("Plotname: {}\n", self.plotname).as_str();
+        ret += match self.flags {
+            Flags::Real => "Flags: real",
+            Flags::Complex => "Flags: complex",
+        };
+        for modifier in self.flag_modifiers.iter() {
+            ret += format!(" {}", modifier).as_str();
+        }
+        ret += "\n";
+        ret += format!("No. Variables: {}\n", self.no_of_variables).as_str();
+        ret += format!("No. Points: {}\n", points).as_str();
+        if let Some(dimensions) = &self.dimensions {
+            let dimensions: Vec<String> = dimensions.iter().map(|dim| dim.to_string()).collect();
+            ret += format!("Dimensions: {}\n", dimensions.join(",")).as_str();
+        }
+        if let Some(command) = &self.command {
+            ret += format!("Command: {}\n", command).as_str();
+        }
+        for (key, value) in self.extra_headers.iter() {
+            ret += format!("{}: {}\n", key, value).as_str();
+        }
+        ret += "Variables:\n";
+        for (idx, var_data) in self.data.iter().enumerate() {
+            ret += format!("\t{}\t{}\t{}", idx, var_data.name, var_data.typee).as_str();
+            for extra in var_data.extra.iter() {
+                ret += format!(" {}", extra).as_str();
+            }
+            ret += "\n";
+        }
+        ret
+    }
+    /// Writes the plot as an ASCII rawfile. Only the first
+    /// [`Plot::actual_points`] points are written when the columns disagree.
+    pub fn to_rawfile(&self) -> String {
+        let points = self.actual_points();
+        let mut ret = self.rawfile_header(points);
+        ret += "Values:\n";
+        for i in 0..points {
+            ret += format!(" {}", i).as_str();
+            for var_data in self.data.iter() {
+                let val = match self.flags {
+                    Flags::Real => format_value(var_data.values[i]),
+                    Flags::Complex => {
+                        let (real, imaginary) = rectangular(var_data, i);
+                        format!("{},{}", format_value(real), format_value(imaginary))
+                    }
+                };
+                ret += format!("\t{}\n", val).as_str();
+            }
+            ret += "\n";
+        }
+        ret
+    }
+    /// Writes the ASCII header followed by a `Binary:` section of
+    /// little-endian `f64` values, interleaving real and imaginary parts for
+    /// complex plots. Like [`Plot::to_rawfile`], only the first
+    /// [`Plot::actual_points`] points are written.
+    pub fn to_binary_rawfile(&self) -> Vec<u8> {
+        let points = self.actual_points();
+        let mut ret: Vec<u8> = self.rawfile_header(points).into_bytes();
+        ret.extend_from_slice(b"Binary:\n");
+        for i in 0..points {
+            for var_data in self.data.iter() {
+                match self.flags {
+                    Flags::Real => ret.extend_from_slice(&var_data.values[i].to_le_bytes()),
+                    Flags::Complex => {
+                        let (real, imaginary) = rectangular(var_data, i);
+                        ret.extend_from_slice(&real.to_le_bytes());
+                        ret.extend_from_slice(&imaginary.to_le_bytes());
+                    }
+                }
+            }
+        }
+        ret
+    }
+    /// Writes a Touchstone file with magnitude/angle data. The first variable
+    /// is the frequency and the rest are taken in plot order as the network
+    /// parameters (`S11 S21 S12 S22` for two ports). From three ports on,
+    /// every matrix row starts a new line, and rows are wrapped after four
+    /// pairs as Touchstone v1 requires.
+    pub fn to_touchstone(&self, ports: usize) -> Result<String, SpiceParseError> {
+        if let Flags::Real = self.flags {
+            return Result::Err(SpiceParseError::NotComplex);
+        }
+        let expected = ports * ports + 1;
+        if self.data.len() != expected {
+            return Result::Err(SpiceParseError::PortMismatch { expected });
+        }
+        let phases: Vec<_> = self.data.iter().map(VarData::phases).collect();
+        let mut ret = String::from("# Hz S MA R 50\n");
+        for i in 0..self.actual_points() {
+            ret += self.data[0].values[i].to_string().as_str();
+            for (idx, var_data) in self.data[1..].iter().enumerate() {
+                if ports > 2 && idx != 0 && (idx % ports).is_multiple_of(4) {
+                    ret += "\n";
+                }
+                let angle = match &phases[idx + 1] {
+                    Some(angles) => angles[i].to_degrees(),
+                    None => 0f64,
+                };
+                ret += format!(" {} {}", var_data.values[i], angle).as_str();
+            }
+            ret += "\n";
+        }
+        Ok(ret)
+    }
+    #[cfg(feature = "npy")]
+    pub fn to_npy(&self) -> Result<Vec<u8>, SpiceParseError> {
+        if self
+            .data
+            .iter()
+            .any(|var_data| var_data.values.len() != self.no_of_points)
+        {
+            return Result::Err(SpiceParseError::NoOfPointMismatch);
+        }
+        let descr = match self.flags {
+            Flags::Real => "<f8",
+            Flags::Complex => "<c16",
+        };
+        let mut header = format!(
+            "{{'descr': '{}', 'fortran_order': False, 'shape': ({}, {}), }}",
+            descr,
+            self.no_of_points,
+            self.data.len()
+        );
+        while (10 + header.len() + 1) % 64 != 0 {
+            header.push(' ');
+        }
+        header.push('\n');
+        let mut ret: Vec<u8> = Vec::from(&b"\x93NUMPY\x01\x00"[..]);
+        ret.extend_from_slice(&(header.len() as u16).to_le_bytes());
+        ret.extend_from_slice(header.as_bytes());
+        for i in 0..self.no_of_points {
+            for var_data in self.data.iter() {
+                match self.flags {
+                    Flags::Real => ret.extend_from_slice(&var_data.values[i].to_le_bytes()),
+                    Flags::Complex => {
+                        let (real, imaginary) = rectangular(var_data, i);
+                        ret.extend_from_slice(&real.to_le_bytes());
+                        ret.extend_from_slice(&imaginary.to_le_bytes());
+                    }
+                }
+            }
+        }
+        Ok(ret)
+    }
+    #[cfg(feature = "arrow")]
+    pub fn to_record_batch(&self) -> Result<arrow_array::RecordBatch, arrow_schema::ArrowError> {
+        use arrow_array::{ArrayRef, Float64Array, RecordBatch};
+        use arrow_schema::{DataType, Field, Schema};
+        use std::sync::Arc;
+        let mut fields: Vec<Field> = Vec::new();
+        let mut columns: Vec<ArrayRef> = Vec::new();
+        for var_data in self.data.iter() {
+            fields.push(Field::new(var_data.name.as_str(), DataType::Float64, false));
+            columns.push(Arc::new(Float64Array::from(var_data.values.clone())));
+            if let Some(angles) = var_data.phases() {
+                let name = format!("{}_phase", var_data.name);
+                fields.push(Field::new(name, DataType::Float64, false));
+                columns.push(Arc::new(Float64Array::from(angles.into_owned())));
+            }
+        }
+        RecordBatch::try_new(Arc::new(Schema::new(fields)), columns)
+    }
+}
@@ -0,0 +1,137 @@
+use crate::{parse_borrowed_with, Flags, ParseOptions, Plot, SpiceParseError, VarData};
+use alloc::collections::BTreeSet;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+/// A cache of shared strings. Passing the same interner to
+/// [`parse_with_interner`] for many rawfiles stores each distinct plotname,
+/// variable name, type and header key only once.
+#[derive(Debug, Clone, Default)]
+pub struct Interner {
+    strings: BTreeSet<Arc<str>>,
+}
+impl Interner {
+    pub fn new() -> Interner {
+        Interner::default()
+    }
+    pub fn intern(&mut self, string: &str) -> Arc<str> {
+        if let Some(shared) = self.strings.get(string) {
+            return shared.clone();
+        }
+        let shared: Arc<str> = Arc::from(string);
+        self.strings.insert(shared.clone());
+        shared
+    }
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+}
+#[derive(Debug, Clone, PartialEq)]
+pub struct InternedVarData {
+    pub name: Arc<str>,
+    pub typee: Arc<str>,
+    pub extra: Vec<String>,
+    pub values: Vec<f64>,
+    pub angles: Option<Vec<f64>>,
+    pub real: Option<Vec<f64>>,
+    pub imag: Option<Vec<f64>>,
+}
+/// A [`Plot`] whose plotname, variable names, types and header keys come
+/// from an [`Interner`]. The strings that differ from file to file, like the
+/// title and the date, are owned. Convert it into a [`Plot`] for the
+/// analysis and export helpers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InternedPlot {
+    pub title: String,
+    pub date: String,
+    pub plotname: Arc<str>,
+    pub flags: Flags,
+    pub flag_modifiers: Vec<String>,
+    pub no_of_variables: usize,
+    pub no_of_points: usize,
+    pub dimensions: Option<Vec<usize>>,
+    pub command: Option<String>,
+    pub extra_headers: Vec<(Arc<str>, String)>,
+    pub point_indices: Vec<usize>,
+    pub data: Vec<InternedVarData>,
+}
+impl From<InternedVarData> for VarData {
+    fn from(var_data: InternedVarData) -> VarData {
+        VarData {
+            name: String::from(&*var_data.name),
+            typee: String::from(&*var_data.typee),
+            extra: var_data.extra,
+            values: var_data.values,
+            angles: var_data.angles,
+            real: var_data.real,
+            imag: var_data.imag,
+        }
+    }
+}
+impl From<InternedPlot> for Plot {
+    fn from(plot: InternedPlot) -> Plot {
+        Plot {
+            title: plot.title,
+            date: plot.date,
+            plotname: String::from(&*plot.plotname),
+            flags: plot.flags,
+            flag_modifiers: plot.flag_modifiers,
+            no_of_variables: plot.no_of_variables,
+            no_of_points: plot.no_of_points,
+            dimensions: plot.dimensions,
+            command: plot.command,
+            extra_headers: plot
+                .extra_headers
+                .into_iter()
+                .map(|(key, value)| (String::from(&*key), value))
+                .collect(),
+            point_indices: plot.point_indices,
+            data: plot.data.into_iter().map(VarData::from).collect(),
+        }
+    }
+}
+/// Parses like [`crate::parse_with`], taking the plotname, variable names,
+/// types and header keys from `interner` instead of allocating them per
+/// plot.
+pub fn parse_with_interner(
+    file: &str,
+    opts: ParseOptions,
+    interner: &mut Interner,
+) -> Result<InternedPlot, SpiceParseError> {
+    let plot = parse_borrowed_with(file, opts, &mut Vec::new())?;
+    let data: Vec<InternedVarData> = plot
+        .data
+        .into_iter()
+        .map(|var_data| InternedVarData {
+            name: interner.intern(var_data.name),
+            typee: interner.intern(var_data.typee),
+            extra: var_data.extra.into_iter().map(String::from).collect(),
+            values: var_data.values,
+            angles: var_data.angles,
+            real: var_data.real,
+            imag: var_data.imag,
+        })
+        .collect();
+    Result::Ok(InternedPlot {
+        title: String::from(plot.title),
+        date: plot.date.into_owned(),
+        plotname: interner.intern(plot.plotname),
+        flags: plot.flags,
+        flag_modifiers: plot.flag_modifiers.into_iter().map(String::from).collect(),
+        no_of_variables: plot.no_of_variables,
+        no_of_points: plot.no_of_points,
+        dimensions: plot.dimensions,
+        command: plot.command.map(String::from),
+        extra_headers: plot
+            .extra_headers
+            .into_iter()
+            .map(|(key, value)| (interner.intern(key), String::from(value)))
+            .collect(),
+        point_indices: plot.point_indices,
+        data,
+    })
+}
@@ -1,201 +1,1818 @@
-use serde::Serialize;
-#[derive(Debug, Clone, Copy, Serialize)]
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+extern crate alloc;
+use alloc::borrow::Cow;
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use alloc::{format, vec};
+use core::iter::{Peekable, Zip};
+use core::ops::RangeFrom;
+use core::str::FromStr;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "std")]
+use std::io::{BufRead, BufReader, Read, Write};
+#[cfg(feature = "std")]
+use std::path::Path;
+mod analysis;
+mod builder;
+mod columnar;
+mod export;
+mod intern;
+mod math;
+mod stream;
+pub use builder::PlotBuilder;
+pub use columnar::ColumnarPlot;
+pub use intern::{parse_with_interner, InternedPlot, InternedVarData, Interner};
+pub use stream::PlotParser;
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum Flags {
     Complex,
     Real,
 }
-#[derive(Debug, Serialize)]
+impl FromStr for Flags {
+    type Err = SpiceParseError;
+    fn from_str(flag: &str) -> Result<Flags, SpiceParseError> {
+        match flag.to_ascii_lowercase().as_str() {
+            "complex" => Result::Ok(Flags::Complex),
+            "real" => Result::Ok(Flags::Real),
+            _ => Result::Err(SpiceParseError::UnknownFlag),
+        }
+    }
+}
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct VarData {
     pub name: String,
     pub typee: String,
+    /// Trailing fields of the variable line after the type, like `grid=3`.
+    #[serde(default)]
+    pub extra: Vec<String>,
     pub values: Vec<f64>,
     pub angles: Option<Vec<f64>>,
+    pub real: Option<Vec<f64>>,
+    pub imag: Option<Vec<f64>>,
 }
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VarKind {
+    Time,
+    Frequency,
+    Voltage,
+    Current,
+    Other(String),
+}
+impl From<&str> for VarKind {
+    fn from(typee: &str) -> VarKind {
+        match typee.to_ascii_lowercase().as_str() {
+            "time" => VarKind::Time,
+            "frequency" => VarKind::Frequency,
+            "voltage" => VarKind::Voltage,
+            "current" => VarKind::Current,
+            _ => VarKind::Other(String::from(typee)),
+        }
+    }
+}
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScaleKind {
+    Time,
+    Frequency,
+    Sweep(String),
+    Unknown,
+}
+impl VarData {
+    pub fn new_real(name: &str, typee: &str, values: Vec<f64>) -> VarData {
+        VarData {
+            name: String::from(name),
+            typee: String::from(typee),
+            extra: Vec::new(),
+            values,
+            angles: None,
+            real: None,
+            imag: None,
+        }
+    }
+    /// Builds a complex variable from its rectangular parts, deriving the
+    /// magnitudes and phases. Fails with `ColumnLengthMismatch` if `real` and
+    /// `imag` differ in length.
+    pub fn new_complex(
+        name: &str,
+        typee: &str,
+        real: Vec<f64>,
+        imag: Vec<f64>,
+    ) -> Result<VarData, SpiceParseError> {
+        if real.len() != imag.len() {
+            return Result::Err(SpiceParseError::ColumnLengthMismatch(String::from(name)));
+        }
+        let (values, angles): (Vec<f64>, Vec<f64>) = real
+            .iter()
+            .zip(imag.iter())
+            .map(|(&re, &im)| to_polar(re, im))
+            .unzip();
+        Result::Ok(VarData {
+            name: String::from(name),
+            typee: String::from(typee),
+            extra: Vec::new(),
+            values,
+            angles: Some(angles),
+            real: Some(real),
+            imag: Some(imag),
+        })
+    }
+    pub fn kind(&self) -> VarKind {
+        VarKind::from(self.typee.as_str())
+    }
+    /// SI unit of the common ngspice variable types, if known.
+    pub fn unit(&self) -> Option<&'static str> {
+        let unit = match self.typee.to_ascii_lowercase().as_str() {
+            "time" => "s",
+            "frequency" => "Hz",
+            "voltage" => "V",
+            "current" => "A",
+            "temperature" | "temp-sweep" => "°C",
+            "impedance" | "res-sweep" => "Ohm",
+            "admittance" => "S",
+            "power" => "W",
+            "phase" => "rad",
+            "decibel" => "dB",
+            "capacitance" => "F",
+            "charge" => "C",
+            _ => return None,
+        };
+        Some(unit)
+    }
+    pub fn magnitude(&self) -> &[f64] {
+        &self.values
+    }
+    /// The stored phases. Complex variables parsed with
+    /// [`ComplexStorage::Rectangular`] have none.
+    pub fn phase(&self) -> Option<&[f64]> {
+        self.angles.as_deref()
+    }
+    /// The phases, derived from `real` and `imag` when they were not stored.
+    pub(crate) fn phases(&self) -> Option<Cow<'_, [f64]>> {
+        if let Option::Some(angles) = &self.angles {
+            return Some(Cow::Borrowed(angles));
+        }
+        let (real, imag) = (self.real.as_ref()?, self.imag.as_ref()?);
+        Some(Cow::Owned(
+            real.iter()
+                .zip(imag.iter())
+                .map(|(&re, &im)| math::atan2(im, re))
+                .collect(),
+        ))
+    }
+    pub fn is_complex(&self) -> bool {
+        self.angles.is_some() || self.imag.is_some()
+    }
+    /// Pointer and length of `values` for handing to C. The pointer stays
+    /// valid until the variable is mutated or dropped.
+    pub fn values_raw(&self) -> (*const f64, usize) {
+        (self.values.as_ptr(), self.values.len())
+    }
+    pub fn angles_raw(&self) -> Option<(*const f64, usize)> {
+        let angles = self.angles.as_ref()?;
+        Some((angles.as_ptr(), angles.len()))
+    }
+    #[cfg(feature = "num-complex")]
+    pub fn complex_values(&self) -> Option<Vec<num_complex::Complex<f64>>> {
+        let (real, imag) = (self.real.as_ref()?, self.imag.as_ref()?);
+        Some(
+            real.iter()
+                .zip(imag.iter())
+                .map(|(&re, &im)| num_complex::Complex::new(re, im))
+                .collect(),
+        )
+    }
+}
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Plot {
     pub title: String,
     pub date: String,
+    /// The first `Plotname:` of the header. Repeats before `Variables:` are
+    /// ignored and reported by [`parse_verbose`].
     pub plotname: String,
     pub flags: Flags,
+    pub flag_modifiers: Vec<String>,
     pub no_of_variables: usize,
     pub no_of_points: usize,
+    pub dimensions: Option<Vec<usize>>,
+    pub command: Option<String>,
+    pub extra_headers: Vec<(String, String)>,
+    #[serde(default)]
+    pub point_indices: Vec<usize>,
     pub data: Vec<VarData>,
 }
+impl Plot {
+    /// The first variable called `name`. Names are not guaranteed to be
+    /// unique; use [`Plot::variables_named`] to see every match.
+    pub fn variable(&self, name: &str) -> Option<&VarData> {
+        self.data.iter().find(|var_data| var_data.name == name)
+    }
+    pub fn variables_named(&self, name: &str) -> Vec<&VarData> {
+        self.data
+            .iter()
+            .filter(|var_data| var_data.name == name)
+            .collect()
+    }
+    pub fn variable_index(&self, name: &str) -> Option<usize> {
+        self.data.iter().position(|var_data| var_data.name == name)
+    }
+    pub fn variable_ci(&self, name: &str) -> Option<&VarData> {
+        self.data
+            .iter()
+            .find(|var_data| var_data.name.eq_ignore_ascii_case(name))
+    }
+    pub fn variables_of_type(&self, typee: &str) -> Vec<&VarData> {
+        self.data
+            .iter()
+            .filter(|var_data| var_data.typee == typee)
+            .collect()
+    }
+    /// Parses `date` in the `Sat Dec 25 20:36:08  2021` form ngspice writes.
+    #[cfg(feature = "chrono")]
+    pub fn datetime(&self) -> Option<chrono::NaiveDateTime> {
+        chrono::NaiveDateTime::parse_from_str(self.date.trim(), "%a %b %e %H:%M:%S %Y").ok()
+    }
+    /// Checks that every column of every variable holds the same number of
+    /// values. The parsers store whole points only, so this is a check for
+    /// plots that were assembled or edited by hand.
+    pub fn validate(&self) -> Result<(), SpiceParseError> {
+        let len = self
+            .data
+            .first()
+            .map_or(0, |var_data| var_data.values.len());
+        for var_data in self.data.iter() {
+            let consistent = var_data.values.len() == len
+                && [&var_data.angles, &var_data.real, &var_data.imag]
+                    .iter()
+                    .all(|column| column.as_ref().is_none_or(|column| column.len() == len));
+            if !consistent {
+                return Result::Err(SpiceParseError::ColumnLengthMismatch(var_data.name.clone()));
+            }
+        }
+        Ok(())
+    }
+    pub fn actual_points(&self) -> usize {
+        self.data
+            .iter()
+            .map(|var_data| var_data.values.len())
+            .min()
+            .unwrap_or(0)
+    }
+    pub fn actual_variables(&self) -> usize {
+        self.data.len()
+    }
+    /// Number of variables, matching what iterating the plot yields.
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+    pub fn points(&self) -> usize {
+        self.no_of_points
+    }
+    pub fn scale_index(&self) -> usize {
+        0
+    }
+    pub fn scale(&self) -> &VarData {
+        &self.data[self.scale_index()]
+    }
+    /// Returns a copy with the variable `name` moved into the scale position,
+    /// so slicing, interpolation and the extremum searches run against it.
+    pub fn with_scale(&self, name: &str) -> Result<Plot, SpiceParseError> {
+        let idx = self
+            .data
+            .iter()
+            .position(|var_data| var_data.name == name)
+            .ok_or_else(|| SpiceParseError::VariableNotFound(String::from(name)))?;
+        let mut plot = self.clone();
+        let scale = plot.data.remove(idx);
+        plot.data.insert(self.scale_index(), scale);
+        Result::Ok(plot)
+    }
+    /// Classifies the scale as time (`.tran`), frequency (`.ac`) or the swept
+    /// quantity of a `.dc` analysis.
+    pub fn scale_kind(&self) -> ScaleKind {
+        let scale = match self.data.get(self.scale_index()) {
+            Option::Some(scale) => scale,
+            Option::None => return ScaleKind::Unknown,
+        };
+        match scale.kind() {
+            VarKind::Time => ScaleKind::Time,
+            VarKind::Frequency => ScaleKind::Frequency,
+            VarKind::Other(typee) if typee.is_empty() || typee.eq_ignore_ascii_case("notype") => {
+                ScaleKind::Unknown
+            }
+            _ => ScaleKind::Sweep(scale.typee.clone()),
+        }
+    }
+}
+impl<'p> IntoIterator for &'p Plot {
+    type Item = &'p VarData;
+    type IntoIter = core::slice::Iter<'p, VarData>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.data.iter()
+    }
+}
+impl core::ops::Index<&str> for Plot {
+    type Output = VarData;
+    fn index(&self, name: &str) -> &VarData {
+        match self.variable(name) {
+            Option::Some(var_data) => var_data,
+            Option::None => panic!("no variable named `{}` in plot", name),
+        }
+    }
+}
+/// Summarizes the header and variable table in rawfile notation, without
+/// the values.
+impl core::fmt::Display for Plot {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let flags = match self.flags {
+            Flags::Real => "real",
+            Flags::Complex => "complex",
+        };
+        writeln!(f, "Title: {}", self.title)?;
+        writeln!(f, "Plotname: {}", self.plotname)?;
+        writeln!(f, "Flags: {}", flags)?;
+        writeln!(f, "No. Variables: {}", self.no_of_variables)?;
+        writeln!(f, "No. Points: {}", self.no_of_points)?;
+        writeln!(f, "Variables:")?;
+        for (idx, var_data) in self.data.iter().enumerate() {
+            writeln!(f, "\t{}\t{}\t{}", idx, var_data.name, var_data.typee)?;
+        }
+        Ok(())
+    }
+}
+impl IntoIterator for Plot {
+    type Item = VarData;
+    type IntoIter = alloc::vec::IntoIter<VarData>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.data.into_iter()
+    }
+}
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Variable {
+    pub name: String,
+    pub typee: String,
+    pub extra: Vec<String>,
+}
+#[derive(Debug, Serialize)]
+pub struct PlotHeader {
+    pub title: String,
+    pub date: String,
+    pub plotname: String,
+    pub flags: Flags,
+    pub flag_modifiers: Vec<String>,
+    pub no_of_variables: usize,
+    pub no_of_points: usize,
+    pub dimensions: Option<Vec<usize>>,
+    pub command: Option<String>,
+    pub extra_headers: Vec<(String, String)>,
+    pub variables: Vec<Variable>,
+}
+#[derive(Debug, PartialEq, Serialize)]
+pub struct VarDataRef<'a> {
+    pub name: &'a str,
+    pub typee: &'a str,
+    pub extra: Vec<&'a str>,
+    pub values: Vec<f64>,
+    pub angles: Option<Vec<f64>>,
+    pub real: Option<Vec<f64>>,
+    pub imag: Option<Vec<f64>>,
+}
+#[derive(Debug, PartialEq, Serialize)]
+pub struct PlotRef<'a> {
+    pub title: &'a str,
+    pub date: Cow<'a, str>,
+    pub plotname: &'a str,
+    pub flags: Flags,
+    pub flag_modifiers: Vec<&'a str>,
+    pub no_of_variables: usize,
+    pub no_of_points: usize,
+    pub dimensions: Option<Vec<usize>>,
+    pub command: Option<&'a str>,
+    pub extra_headers: Vec<(&'a str, &'a str)>,
+    pub point_indices: Vec<usize>,
+    pub data: Vec<VarDataRef<'a>>,
+}
 
 #[derive(thiserror::Error, Debug)]
 pub enum SpiceParseError {
     #[error("Cannot parse integer")]
-    ParseInt(#[from] std::num::ParseIntError),
+    ParseInt(#[from] core::num::ParseIntError),
     #[error("Cannot parse float")]
-    ParseFloat(#[from] std::num::ParseFloatError),
+    ParseFloat(#[from] core::num::ParseFloatError),
+    #[cfg(feature = "std")]
+    #[error("Cannot read input")]
+    Io(#[from] std::io::Error),
+    #[error("Input is not valid UTF-8")]
+    Utf8(#[from] core::str::Utf8Error),
     #[error("Number of variables mismatch")]
     NoOfVarMismatch,
     #[error("Number of values mismatch")]
     NoOfValMismatch,
+    #[error("Number of points mismatch")]
+    NoOfPointMismatch,
+    #[error("Dimensions do not match number of points")]
+    DimensionsMismatch,
     #[error("Unknown value in flags")]
     UnknownFlag,
+    #[error("{source} at line {line}")]
+    At {
+        line: usize,
+        source: Box<SpiceParseError>,
+    },
+    #[error("Binary section not found")]
+    NoBinarySection,
+    #[error("Binary data is truncated")]
+    TruncatedBinary,
+    #[error("Plot is not complex")]
+    NotComplex,
+    #[error("Expected {expected} variables for the requested number of ports")]
+    PortMismatch { expected: usize },
+    #[error("Variable line {line} needs an index, a name and a type")]
+    MalformedVariableLine { line: usize },
+    #[error("Complex value at line {line} needs a real and an imaginary part")]
+    MalformedComplexValue { line: usize },
+    #[error("Variable `{0}` not found")]
+    VariableNotFound(String),
+    #[error("Variable `{0}` has a different number of values than the scale")]
+    ColumnLengthMismatch(String),
+    #[error("Rawfile header has no `{0}`")]
+    MissingHeader(&'static str),
+    #[error("Scales of merged plots do not match")]
+    ScaleMismatch,
+    #[cfg(feature = "json")]
+    #[error("Cannot serialize to JSON")]
+    Json(#[from] serde_json::Error),
 }
-fn flush_values(
-    no_of_variables: usize,
-    temp_values: &mut Vec<(f64, f64)>,
-    data: &mut Vec<VarData>,
+/// A non-fatal problem noticed by [`parse_verbose`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseWarning {
+    pub line: usize,
+    pub message: String,
+}
+impl ParseWarning {
+    fn new(line: usize, message: String) -> ParseWarning {
+        ParseWarning { line, message }
+    }
+}
+impl SpiceParseError {
+    fn at(self, line: usize) -> SpiceParseError {
+        SpiceParseError::At {
+            line,
+            source: Box::new(self),
+        }
+    }
+    fn value_mismatch_line(&self) -> Option<usize> {
+        match self {
+            SpiceParseError::At { line, source } => {
+                matches!(**source, SpiceParseError::NoOfValMismatch).then_some(*line)
+            }
+            _ => None,
+        }
+    }
+}
+fn parse_at<T: FromStr>(num: &str, line: usize) -> Result<T, SpiceParseError>
+where
+    SpiceParseError: From<T::Err>,
+{
+    num.parse()
+        .map_err(|err| SpiceParseError::from(err).at(line))
+}
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ParseOptions {
+    pub spice_suffixes: bool,
+    /// Drop points with the wrong number of values, resuming at the next
+    /// index line, and keep a plot with fewer points than announced. Both
+    /// are reported as warnings instead of errors.
+    pub lenient: bool,
+    pub complex_as: ComplexStorage,
+}
+/// How [`parse_with`] stores the values of complex plots.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ComplexStorage {
+    /// Magnitudes in `values` and phases in `angles`, next to `real` and
+    /// `imag`.
+    #[default]
+    Polar,
+    /// Magnitudes in `values` next to `real` and `imag`, skipping the phases.
+    /// `angles` is `None`, and the helpers that need phases derive them from
+    /// `real` and `imag`.
+    Rectangular,
+}
+const SPICE_SUFFIXES: [(&str, f64); 9] = [
+    ("meg", 1e6),
+    ("f", 1e-15),
+    ("p", 1e-12),
+    ("n", 1e-9),
+    ("u", 1e-6),
+    ("m", 1e-3),
+    ("k", 1e3),
+    ("g", 1e9),
+    ("t", 1e12),
+];
+fn parse_value(num: &str, line: usize, opts: ParseOptions) -> Result<f64, SpiceParseError> {
+    if opts.spice_suffixes {
+        if let Result::Ok(value) = num.parse::<f64>() {
+            return Result::Ok(value);
+        }
+        let lower = num.to_ascii_lowercase();
+        for (suffix, scale) in SPICE_SUFFIXES {
+            if let Option::Some(mantissa) = lower.strip_suffix(suffix) {
+                if let Result::Ok(value) = mantissa.parse::<f64>() {
+                    return Result::Ok(value * scale);
+                }
+            }
+        }
+    }
+    parse_at(num, line)
+}
+enum Section {
+    Values,
+    Binary,
+    End,
+}
+type ColumnsMut<'c> = (
+    &'c mut Vec<f64>,
+    &'c mut Option<Vec<f64>>,
+    &'c mut Option<Vec<f64>>,
+    &'c mut Option<Vec<f64>>,
+);
+trait Columns {
+    fn columns(&mut self) -> ColumnsMut<'_>;
+    fn push_sample(&mut self, sample: Sample) {
+        let (values, angles, real, imag) = self.columns();
+        values.push(sample.value);
+        for (column, part) in [
+            (angles, sample.angle),
+            (real, sample.real),
+            (imag, sample.imag),
+        ] {
+            if let (Option::Some(column), Option::Some(part)) = (column, part) {
+                column.push(part);
+            }
+        }
+    }
+}
+impl Columns for VarData {
+    fn columns(&mut self) -> ColumnsMut<'_> {
+        (
+            &mut self.values,
+            &mut self.angles,
+            &mut self.real,
+            &mut self.imag,
+        )
+    }
+}
+impl Columns for VarDataRef<'_> {
+    fn columns(&mut self) -> ColumnsMut<'_> {
+        (
+            &mut self.values,
+            &mut self.angles,
+            &mut self.real,
+            &mut self.imag,
+        )
+    }
+}
+fn new_column(flags: Flags, capacity: usize) -> Option<Vec<f64>> {
+    match flags {
+        Flags::Real => None,
+        Flags::Complex => Some(Vec::with_capacity(capacity)),
+    }
+}
+/// One variable's share of a point, ready to be appended to its columns.
+#[derive(Debug, Clone, Copy)]
+struct Sample {
+    value: f64,
+    angle: Option<f64>,
+    real: Option<f64>,
+    imag: Option<f64>,
+}
+/// Decodes a row as read by `read_point` into one sample per variable. When
+/// `starts_with_scale` is set, the first variable is the scale, and a real
+/// value ngspice wrote as `1000,0` is kept as its real part with a zero phase.
+fn decode_point(
+    row: &[f64],
     flags: Flags,
-) -> Result<(), SpiceParseError> {
-    if temp_values.len() != 0 {
-        if temp_values.len() != no_of_variables {
-            return Result::Err(SpiceParseError::NoOfValMismatch);
-        }
-        let mut idx: usize = 0;
-        for val in temp_values.iter() {
-            data[idx].values.push(val.0);
-            if let Flags::Complex = flags {
-                if let Option::Some(vec) = &mut data[idx].angles {
-                    vec.push(val.1);
+    starts_with_scale: bool,
+    storage: ComplexStorage,
+) -> impl Iterator<Item = Sample> + '_ {
+    row.chunks_exact(row_len(1, flags))
+        .enumerate()
+        .map(move |(idx, parts)| {
+            if let Flags::Real = flags {
+                return Sample {
+                    value: parts[0],
+                    angle: None,
+                    real: None,
+                    imag: None,
+                };
+            }
+            let (real, imaginary) = (parts[0], parts[1]);
+            let real_scale = starts_with_scale && idx == 0 && imaginary == 0.0;
+            let (magnitude, phase) = match (storage, real_scale) {
+                (ComplexStorage::Polar, true) => (real, Some(0f64)),
+                (ComplexStorage::Polar, false) => {
+                    let (magnitude, phase) = to_polar(real, imaginary);
+                    (magnitude, Some(phase))
+                }
+                (ComplexStorage::Rectangular, true) => (real, None),
+                (ComplexStorage::Rectangular, false) => {
+                    (math::sqrt(real * real + imaginary * imaginary), None)
                 }
+            };
+            Sample {
+                value: magnitude,
+                angle: phase,
+                real: Some(real),
+                imag: Some(imaginary),
             }
-            idx += 1;
+        })
+}
+fn flush_values<C: Columns>(row: &[f64], data: &mut [C], flags: Flags, starts_with_scale: bool) {
+    flush_values_as(row, data, flags, starts_with_scale, ComplexStorage::Polar)
+}
+fn flush_values_as<C: Columns>(
+    row: &[f64],
+    data: &mut [C],
+    flags: Flags,
+    starts_with_scale: bool,
+    storage: ComplexStorage,
+) {
+    let samples = decode_point(row, flags, starts_with_scale, storage);
+    for (var_data, sample) in data.iter_mut().zip(samples) {
+        var_data.push_sample(sample);
+    }
+}
+fn push_index(indices: &mut Vec<usize>, index: Option<usize>) {
+    indices.push(index.unwrap_or_else(|| indices.last().map_or(0, |last| last.saturating_add(1))));
+}
+fn row_len(no_of_variables: usize, flags: Flags) -> usize {
+    match flags {
+        Flags::Real => no_of_variables,
+        Flags::Complex => 2 * no_of_variables,
+    }
+}
+fn to_polar(real: f64, imaginary: f64) -> (f64, f64) {
+    (
+        math::sqrt(real * real + imaginary * imaginary),
+        math::atan2(imaginary, real),
+    )
+}
+struct HeaderRef<'a> {
+    title: &'a str,
+    date: Cow<'a, str>,
+    plotname: &'a str,
+    flags: Flags,
+    flag_modifiers: Vec<&'a str>,
+    no_of_variables: usize,
+    no_of_points: usize,
+    dimensions: Option<Vec<usize>>,
+    command: Option<&'a str>,
+    extra_headers: Vec<(&'a str, &'a str)>,
+    variables: Vec<(&'a str, &'a str, Vec<&'a str>)>,
+    warnings: Vec<ParseWarning>,
+}
+impl HeaderRef<'_> {
+    fn into_owned(self) -> PlotHeader {
+        PlotHeader {
+            title: String::from(self.title),
+            date: self.date.into_owned(),
+            plotname: String::from(self.plotname),
+            flags: self.flags,
+            flag_modifiers: self.flag_modifiers.into_iter().map(String::from).collect(),
+            no_of_variables: self.no_of_variables,
+            no_of_points: self.no_of_points,
+            dimensions: self.dimensions,
+            command: self.command.map(String::from),
+            extra_headers: self
+                .extra_headers
+                .into_iter()
+                .map(|(key, value)| (String::from(key), String::from(value)))
+                .collect(),
+            variables: self
+                .variables
+                .into_iter()
+                .map(|(name, typee, extra)| Variable {
+                    name: String::from(name),
+                    typee: String::from(typee),
+                    extra: extra.into_iter().map(String::from).collect(),
+                })
+                .collect(),
         }
-        temp_values.clear();
     }
-    Ok(())
 }
-pub fn parse(file: &str) -> Result<Plot, SpiceParseError> {
-    let mut title: String = String::new();
-    let mut date: String = String::new();
-    let mut plotname: String = String::new();
+fn parse_header<'a, I: Iterator<Item = (usize, &'a str)>>(
+    lines: &mut I,
+) -> Result<(HeaderRef<'a>, Section), SpiceParseError> {
+    let mut title: &str = "";
+    let mut date: Cow<str> = Cow::Borrowed("");
+    let mut plotname: &str = "";
+    let mut plotname_seen: bool = false;
     let mut flags: Flags = Flags::Real;
+    let mut flag_modifiers: Vec<&str> = Vec::new();
     let mut no_of_variables: usize = 0;
     let mut no_of_points: usize = 0;
-    let mut data: Vec<VarData> = Vec::new();
+    let mut dimensions: Option<(usize, Vec<usize>)> = None;
+    let mut command: Option<&str> = None;
+    let mut extra_headers: Vec<(&str, &str)> = Vec::new();
+    let mut variables: Vec<(&str, &str, Vec<&str>)> = Vec::new();
+    let mut warnings: Vec<ParseWarning> = Vec::new();
     enum Modes {
         Meta,
         Variable,
-        Value,
     }
     let mut mode: Modes = Modes::Meta;
     let mut variable_counter: usize = 0;
-    let mut temp_values: Vec<(f64, f64)> = Vec::new();
-    for lin in file.lines() {
-        if lin.trim().len() == 0 {
+    let mut section: Section = Section::End;
+    let mut section_line: usize = 0;
+    let (mut flags_seen, mut variables_seen, mut points_seen) = (false, false, false);
+    for (line, lin) in lines {
+        let lin = lin.strip_prefix('\u{FEFF}').unwrap_or(lin);
+        if lin.trim().is_empty() {
             continue;
         }
         match mode {
             Modes::Meta => {
-                let parts: Vec<&str> = lin.trim().split(':').collect();
-                match parts[0] {
-                    "Title" => title = String::from(parts[1].trim()),
-                    "Date" => date = String::from(parts[1..].join("").trim()),
-                    "Plotname" => plotname = String::from(parts[1].trim()),
+                let (key, value) = lin.trim().split_once(':').unwrap_or((lin.trim(), ""));
+                let value = value.trim();
+                match key {
+                    "Title" => title = value,
+                    "Date" => date = Cow::Borrowed(value),
+                    "Plotname" if plotname_seen => {
+                        warnings.push(ParseWarning::new(
+                            line,
+                            format!("Repeated Plotname `{}` ignored", value),
+                        ));
+                    }
+                    "Plotname" => {
+                        plotname = value;
+                        plotname_seen = true;
+                    }
+                    "Command" => command = Some(value),
                     "Flags" => {
-                        flags = match parts[1].trim() {
-                            "complex" => Flags::Complex,
-                            "real" => Flags::Real,
-                            _ => {
-                                return Result::Err(SpiceParseError::UnknownFlag);
+                        flags_seen = true;
+                        flags = Flags::Real;
+                        for token in value.split_whitespace() {
+                            match token.parse::<Flags>() {
+                                Result::Ok(parsed) => flags = parsed,
+                                Result::Err(_) => {
+                                    warnings.push(ParseWarning::new(
+                                        line,
+                                        format!("Unrecognized flag `{}`", token),
+                                    ));
+                                    flag_modifiers.push(token)
+                                }
                             }
                         }
                     }
-                    "No. Variables" => no_of_variables = parts[1].trim().parse()?,
-                    "No. Points" => no_of_points = parts[1].trim().parse()?,
+                    "No. Variables" => {
+                        no_of_variables = parse_at(value, line)?;
+                        variables_seen = true;
+                    }
+                    "No. Points" => {
+                        no_of_points = parse_at(value, line)?;
+                        points_seen = true;
+                    }
+                    "Dimensions" => {
+                        let mut dims: Vec<usize> = Vec::new();
+                        for dim in value.split(',') {
+                            dims.push(parse_at(dim.trim(), line)?);
+                        }
+                        dimensions = Some((line, dims));
+                    }
                     "Variables" => mode = Modes::Variable,
-                    "Values" => mode = Modes::Value,
-                    _ => {}
+                    "Values" => {
+                        (section, section_line) = (Section::Values, line);
+                        break;
+                    }
+                    "Binary" => {
+                        (section, section_line) = (Section::Binary, line);
+                        break;
+                    }
+                    _ => {
+                        if lin.contains(':') {
+                            warnings.push(ParseWarning::new(
+                                line,
+                                format!("Unknown header `{}`", key.trim()),
+                            ));
+                            extra_headers.push((key.trim(), value));
+                        } else {
+                            warnings.push(ParseWarning::new(
+                                line,
+                                String::from("Ignored line without a header key"),
+                            ));
+                        }
+                    }
                 };
             }
             Modes::Variable => {
-                if variable_counter == no_of_variables {
-                    return Result::Err(SpiceParseError::NoOfVarMismatch);
+                let key = lin.trim().split(':').next();
+                let table_ended = matches!(key, Some("Values") | Some("Binary"));
+                if variable_counter == no_of_variables || table_ended {
+                    return Result::Err(SpiceParseError::NoOfVarMismatch.at(line));
                 }
                 variable_counter += 1;
 
                 if variable_counter == no_of_variables {
                     mode = Modes::Meta;
                 }
-                let parts: Vec<&str> = lin.trim().split("\t").collect();
-                data.push(VarData {
-                    name: String::from(parts[1].trim()),
-                    typee: String::from(parts[2].trim()),
-                    values: Vec::new(),
-                    angles: match flags {
-                        Flags::Real => None,
-                        Flags::Complex => Some(Vec::new()),
-                    },
-                })
-            }
-            Modes::Value => {
-                let parts: Vec<&str> = lin.trim().split('\t').collect();
-                let mut num = parts[0];
-                if parts.len() == 2 {
-                    flush_values(no_of_variables, &mut temp_values, &mut data, flags)?;
-                    num = parts[1];
+                // ngspice separates the columns with tabs, so a name may
+                // contain spaces; fall back to whitespace for other layouts.
+                let columns: Vec<&str> = lin.trim().splitn(3, '\t').map(str::trim).collect();
+                let parts: Vec<&str> = match columns.len() == 3 && !columns.contains(&"") {
+                    true => columns[..2]
+                        .iter()
+                        .copied()
+                        .chain(columns[2].split_whitespace())
+                        .collect(),
+                    false => lin.split_whitespace().collect(),
                 };
-                let val = match flags {
-                    Flags::Real => (num.parse()?, 0f64),
-                    Flags::Complex => {
-                        let pts: Vec<&str> = num.split(",").collect();
-                        let real: f64 = pts[0].parse()?;
-                        let imaginary: f64 = pts[1].parse()?;
-                        (
-                            (real.powi(2) + imaginary.powi(2)).sqrt(),
-                            (imaginary / real).atan(),
-                        )
-                    }
-                };
-                temp_values.push(val);
+                if parts.len() < 3 {
+                    return Result::Err(SpiceParseError::MalformedVariableLine { line });
+                }
+                variables.push((parts[1], parts[2], parts[3..].to_vec()))
             }
         };
     }
-    flush_values(no_of_variables, &mut temp_values, &mut data, flags)?;
-    Result::Ok(Plot {
+    let missing = [
+        (flags_seen, "Flags"),
+        (variables_seen, "No. Variables"),
+        (points_seen, "No. Points"),
+        (!matches!(section, Section::End), "Values"),
+    ];
+    if let Some((_, key)) = missing.iter().find(|(seen, _)| !seen) {
+        return Result::Err(SpiceParseError::MissingHeader(key));
+    }
+    if variables.len() != no_of_variables {
+        return Result::Err(SpiceParseError::NoOfVarMismatch.at(section_line));
+    }
+    if let Some((line, dims)) = &dimensions {
+        let product = dims
+            .iter()
+            .try_fold(1usize, |acc, dim| acc.checked_mul(*dim));
+        if product != Some(no_of_points) {
+            return Result::Err(SpiceParseError::DimensionsMismatch.at(*line));
+        }
+    }
+    let header = HeaderRef {
         title,
         date,
         plotname,
         flags,
+        flag_modifiers,
         no_of_variables,
         no_of_points,
+        dimensions: dimensions.map(|(_, dims)| dims),
+        command,
+        extra_headers,
+        variables,
+        warnings,
+    };
+    Ok((header, section))
+}
+fn parse_owned_header<S: AsRef<str>, I: Iterator<Item = (usize, S)>>(
+    lines: &mut I,
+    warnings: &mut Vec<ParseWarning>,
+) -> Result<(PlotHeader, Section), SpiceParseError> {
+    let mut header_lines: Vec<(usize, S)> = Vec::new();
+    for (line, lin) in lines.by_ref() {
+        let key = lin.as_ref().trim().split(':').next();
+        let last = matches!(key, Some("Values") | Some("Binary"));
+        header_lines.push((line, lin));
+        if last {
+            break;
+        }
+    }
+    let mut header_lines = header_lines.iter().map(|(line, lin)| (*line, lin.as_ref()));
+    let (mut header, section) = parse_header(&mut header_lines)?;
+    warnings.append(&mut header.warnings);
+    Ok((header.into_owned(), section))
+}
+/// Upper bound on the points reserved up front when the input size is unknown.
+const MAX_RESERVED_POINTS: usize = 1 << 16;
+/// Points to reserve room for. `No. Points` comes from the file, so it is
+/// bounded by `max_points`, the most points the rest of the input can hold,
+/// to keep a bogus header from aborting on an impossible allocation.
+fn point_capacity(no_of_points: usize, max_points: Option<usize>) -> usize {
+    no_of_points.min(max_points.unwrap_or(MAX_RESERVED_POINTS))
+}
+/// `max_points` of an ASCII section with at most `max_lines` lines left.
+fn ascii_capacity(
+    (no_of_variables, no_of_points): (usize, usize),
+    max_lines: Option<usize>,
+) -> usize {
+    let max_points = max_lines.map(|lines| lines / no_of_variables.max(1));
+    point_capacity(no_of_points, max_points)
+}
+fn new_var_data(variable: Variable, flags: Flags, capacity: usize) -> VarData {
+    VarData {
+        name: variable.name,
+        typee: variable.typee,
+        extra: variable.extra,
+        values: Vec::with_capacity(capacity),
+        angles: new_column(flags, capacity),
+        real: new_column(flags, capacity),
+        imag: new_column(flags, capacity),
+    }
+}
+fn new_plot(header: PlotHeader, capacity: usize) -> Plot {
+    let flags = header.flags;
+    let data: Vec<VarData> = header
+        .variables
+        .into_iter()
+        .map(|variable| new_var_data(variable, flags, capacity))
+        .collect();
+    Plot {
+        title: header.title,
+        date: header.date,
+        plotname: header.plotname,
+        flags,
+        flag_modifiers: header.flag_modifiers,
+        no_of_variables: header.no_of_variables,
+        no_of_points: header.no_of_points,
+        dimensions: header.dimensions,
+        command: header.command,
+        extra_headers: header.extra_headers,
+        point_indices: Vec::with_capacity(capacity),
         data,
-    })
+    }
+}
+fn read_point<S: AsRef<str>, I: Iterator<Item = (usize, S)>>(
+    lines: &mut Peekable<I>,
+    no_of_variables: usize,
+    flags: Flags,
+    row: &mut Vec<f64>,
+    index: &mut Option<usize>,
+    opts: ParseOptions,
+) -> Result<Option<usize>, SpiceParseError> {
+    row.clear();
+    *index = None;
+    let mut last_line: usize = 0;
+    let expected = row_len(no_of_variables, flags);
+    while let Some((line, lin)) = lines.peek() {
+        let (line, lin) = (*line, lin.as_ref());
+        if lin.contains(':') || (expected > 0 && row.len() == expected) {
+            break;
+        }
+        if lin.trim().is_empty() {
+            lines.next();
+            continue;
+        }
+        let mut parts: Vec<&str> = lin.split_whitespace().collect();
+        if let (true, Result::Ok(idx)) = (parts.len() > 1, parts[0].parse::<usize>()) {
+            if !row.is_empty() {
+                return Result::Err(SpiceParseError::NoOfValMismatch.at(line));
+            }
+            *index = Some(idx);
+            parts.remove(0);
+        }
+        match flags {
+            Flags::Real if parts.len() != 1 => {
+                lines.next();
+                return Result::Err(SpiceParseError::NoOfValMismatch.at(line));
+            }
+            Flags::Real => row.push(parse_value(parts[0], line, opts)?),
+            Flags::Complex => {
+                // A space after the comma splits `re, im` into two tokens.
+                let num = parts.concat();
+                let pts: Vec<&str> = num.split(',').collect();
+                if pts.len() != 2 {
+                    return Result::Err(SpiceParseError::MalformedComplexValue { line });
+                }
+                row.push(parse_value(pts[0], line, opts)?);
+                row.push(parse_value(pts[1], line, opts)?);
+            }
+        };
+        last_line = line;
+        lines.next();
+    }
+    if !row.is_empty() && row.len() != expected {
+        return Result::Err(SpiceParseError::NoOfValMismatch.at(last_line));
+    }
+    Ok((!row.is_empty()).then_some(last_line))
+}
+/// Whether `lin` opens a point with its index, like ` 3\t4.0e-03`.
+fn starts_point(lin: &str) -> bool {
+    let mut parts = lin.split_whitespace();
+    let index = parts.next().map(str::parse::<usize>);
+    matches!((index, parts.next()), (Some(Ok(_)), Some(_)))
+}
+/// In lenient mode, drops the point a value mismatch was found in and skips
+/// to the next index line, so the points after it are still read. Returns the
+/// line of the mismatch, which is the index line of the following point when
+/// a point ended early.
+fn recover_values<S: AsRef<str>, I: Iterator<Item = (usize, S)>>(
+    err: SpiceParseError,
+    lines: &mut Peekable<I>,
+    opts: ParseOptions,
+    warnings: &mut Vec<ParseWarning>,
+) -> Result<usize, SpiceParseError> {
+    match err.value_mismatch_line() {
+        Some(line) if opts.lenient => {
+            warnings.push(ParseWarning::new(
+                line,
+                String::from("Dropped a point with the wrong number of values"),
+            ));
+            while lines
+                .next_if(|(_, lin)| !(starts_point(lin.as_ref()) || lin.as_ref().contains(':')))
+                .is_some()
+            {}
+            Ok(line)
+        }
+        _ => Result::Err(err),
+    }
+}
+fn check_points(
+    points_read: usize,
+    no_of_points: usize,
+    last_line: usize,
+    opts: ParseOptions,
+    warnings: &mut Vec<ParseWarning>,
+) -> Result<(), SpiceParseError> {
+    if points_read == no_of_points {
+        return Ok(());
+    }
+    if !opts.lenient {
+        return Result::Err(SpiceParseError::NoOfPointMismatch);
+    }
+    warnings.push(ParseWarning::new(
+        last_line,
+        format!("Expected {} points but read {}", no_of_points, points_read),
+    ));
+    Ok(())
+}
+/// Reads points until the section ends, returning how many were stored and
+/// the last line read.
+fn read_values<C: Columns, S: AsRef<str>, I: Iterator<Item = (usize, S)>>(
+    data: &mut [C],
+    indices: &mut Vec<usize>,
+    (no_of_variables, flags): (usize, Flags),
+    lines: &mut Peekable<I>,
+    opts: ParseOptions,
+    warnings: &mut Vec<ParseWarning>,
+) -> Result<(usize, usize), SpiceParseError> {
+    let mut row: Vec<f64> = Vec::with_capacity(row_len(no_of_variables, flags));
+    let mut index: Option<usize> = None;
+    let mut points_read: usize = 0;
+    let mut last_line: usize = 0;
+    loop {
+        match read_point(lines, no_of_variables, flags, &mut row, &mut index, opts) {
+            Ok(Some(line)) => last_line = line,
+            Ok(None) => break,
+            Err(err) => {
+                last_line = recover_values(err, lines, opts, warnings)?;
+                continue;
+            }
+        }
+        flush_values_as(&row, data, flags, true, opts.complex_as);
+        push_index(indices, index);
+        points_read += 1;
+    }
+    Ok((points_read, last_line))
+}
+#[cfg(not(feature = "rayon"))]
+fn parse_values<C: Columns, S: AsRef<str> + Sync, I: Iterator<Item = (usize, S)>>(
+    data: &mut [C],
+    indices: &mut Vec<usize>,
+    (no_of_variables, no_of_points, flags): (usize, usize, Flags),
+    lines: &mut Peekable<I>,
+    opts: ParseOptions,
+    warnings: &mut Vec<ParseWarning>,
+) -> Result<usize, SpiceParseError> {
+    let shape = (no_of_variables, flags);
+    let (points_read, last_line) = read_values(data, indices, shape, lines, opts, warnings)?;
+    check_points(points_read, no_of_points, last_line, opts, warnings)?;
+    Ok(points_read)
+}
+#[cfg(feature = "rayon")]
+fn parse_values<C: Columns, S: AsRef<str> + Sync, I: Iterator<Item = (usize, S)>>(
+    data: &mut [C],
+    indices: &mut Vec<usize>,
+    (no_of_variables, no_of_points, flags): (usize, usize, Flags),
+    lines: &mut Peekable<I>,
+    opts: ParseOptions,
+    warnings: &mut Vec<ParseWarning>,
+) -> Result<usize, SpiceParseError> {
+    use rayon::prelude::*;
+    let mut section: Vec<(usize, S)> = Vec::new();
+    while let Some(item) = lines.next_if(|(_, lin)| !lin.as_ref().contains(':')) {
+        if !item.1.as_ref().trim().is_empty() {
+            section.push(item);
+        }
+    }
+    type ParsedRow = (Vec<f64>, Option<usize>, usize);
+    let rows: Vec<Result<ParsedRow, SpiceParseError>> = section
+        .par_chunks(no_of_variables.max(1))
+        .map(|chunk| {
+            let mut point = chunk
+                .iter()
+                .map(|(line, lin)| (*line, lin.as_ref()))
+                .peekable();
+            let mut row: Vec<f64> = Vec::with_capacity(row_len(no_of_variables, flags));
+            let mut index: Option<usize> = None;
+            let line = read_point(
+                &mut point,
+                no_of_variables,
+                flags,
+                &mut row,
+                &mut index,
+                opts,
+            )?;
+            Ok((row, index, line.unwrap_or(0)))
+        })
+        .collect();
+    let mut points_read: usize = 0;
+    let mut last_line: usize = 0;
+    for (chunk, row) in rows.into_iter().enumerate() {
+        let (row, index, line) = match row {
+            Ok(row) => row,
+            // Points are chunked by line count, so after a dropped point the
+            // chunks no longer line up; read the rest of the section in order.
+            Err(err) if opts.lenient && err.value_mismatch_line().is_some() => {
+                let mut rest = section[chunk * no_of_variables.max(1)..]
+                    .iter()
+                    .map(|(line, lin)| (*line, lin.as_ref()))
+                    .peekable();
+                let shape = (no_of_variables, flags);
+                let (read, line) = read_values(data, indices, shape, &mut rest, opts, warnings)?;
+                points_read += read;
+                last_line = last_line.max(line);
+                break;
+            }
+            Err(err) => return Result::Err(err),
+        };
+        flush_values_as(&row, data, flags, true, opts.complex_as);
+        push_index(indices, index);
+        points_read += 1;
+        last_line = line;
+    }
+    check_points(points_read, no_of_points, last_line, opts, warnings)?;
+    Ok(points_read)
+}
+/// `max_lines` bounds the number of lines, such as the input's length in
+/// bytes, and is `None` when the input size is unknown.
+fn parse_all_lines<S: AsRef<str> + Sync, I: Iterator<Item = (usize, S)>>(
+    lines: I,
+    max_lines: Option<usize>,
+    opts: ParseOptions,
+    warnings: &mut Vec<ParseWarning>,
+) -> Result<Vec<Plot>, SpiceParseError> {
+    let mut lines = lines.peekable();
+    let mut plots: Vec<Plot> = Vec::new();
+    loop {
+        let (header, _) = parse_owned_header(&mut lines, warnings)?;
+        let capacity = ascii_capacity((header.no_of_variables, header.no_of_points), max_lines);
+        let mut plot = new_plot(header, capacity);
+        if opts.complex_as == ComplexStorage::Rectangular {
+            plot.data
+                .iter_mut()
+                .for_each(|var_data| var_data.angles = None);
+        }
+        let shape = (plot.no_of_variables, plot.no_of_points, plot.flags);
+        plot.no_of_points = parse_values(
+            &mut plot.data,
+            &mut plot.point_indices,
+            shape,
+            &mut lines,
+            opts,
+            warnings,
+        )?;
+        plots.push(plot);
+        while lines
+            .next_if(|(_, lin)| lin.as_ref().trim().is_empty())
+            .is_some()
+        {}
+        if lines.peek().is_none() {
+            break;
+        }
+    }
+    Result::Ok(plots)
+}
+pub fn parse_all(file: &str) -> Result<Vec<Plot>, SpiceParseError> {
+    parse_all_lines(
+        (1..).zip(file.lines()),
+        Some(file.len()),
+        ParseOptions::default(),
+        &mut Vec::new(),
+    )
 }
+pub fn parse(file: &str) -> Result<Plot, SpiceParseError> {
+    let mut plots = parse_all(file)?;
+    Result::Ok(plots.swap_remove(0))
+}
+pub fn parse_with(file: &str, opts: ParseOptions) -> Result<Plot, SpiceParseError> {
+    let mut plots = parse_all_lines(
+        (1..).zip(file.lines()),
+        Some(file.len()),
+        opts,
+        &mut Vec::new(),
+    )?;
+    Result::Ok(plots.swap_remove(0))
+}
+/// Parses like [`parse`] and also returns the oddities that were tolerated
+/// on the way, such as unknown header keys or flags.
+pub fn parse_verbose(file: &str) -> Result<(Plot, Vec<ParseWarning>), SpiceParseError> {
+    parse_verbose_with(file, ParseOptions::default())
+}
+/// [`parse_verbose`] with options, which in lenient mode also reports the
+/// point count mismatches that were recovered from.
+pub fn parse_verbose_with(
+    file: &str,
+    opts: ParseOptions,
+) -> Result<(Plot, Vec<ParseWarning>), SpiceParseError> {
+    let mut warnings: Vec<ParseWarning> = Vec::new();
+    let mut plots = parse_all_lines(
+        (1..).zip(file.lines()),
+        Some(file.len()),
+        opts,
+        &mut warnings,
+    )?;
+    Result::Ok((plots.swap_remove(0), warnings))
+}
+pub fn parse_borrowed(file: &str) -> Result<PlotRef<'_>, SpiceParseError> {
+    parse_borrowed_with(file, ParseOptions::default(), &mut Vec::new())
+}
+fn parse_borrowed_with<'a>(
+    file: &'a str,
+    opts: ParseOptions,
+    warnings: &mut Vec<ParseWarning>,
+) -> Result<PlotRef<'a>, SpiceParseError> {
+    let mut lines = (1..).zip(file.lines()).peekable();
+    let (mut header, _) = parse_header(&mut lines)?;
+    warnings.append(&mut header.warnings);
+    let shape = (header.no_of_variables, header.no_of_points);
+    let (flags, capacity) = (header.flags, ascii_capacity(shape, Some(file.len())));
+    let data: Vec<VarDataRef> = header
+        .variables
+        .into_iter()
+        .map(|(name, typee, extra)| VarDataRef {
+            name,
+            typee,
+            extra,
+            values: Vec::with_capacity(capacity),
+            angles: match opts.complex_as {
+                ComplexStorage::Polar => new_column(flags, capacity),
+                ComplexStorage::Rectangular => None,
+            },
+            real: new_column(flags, capacity),
+            imag: new_column(flags, capacity),
+        })
+        .collect();
+    let mut plot = PlotRef {
+        title: header.title,
+        date: header.date,
+        plotname: header.plotname,
+        flags,
+        flag_modifiers: header.flag_modifiers,
+        no_of_variables: header.no_of_variables,
+        no_of_points: header.no_of_points,
+        dimensions: header.dimensions,
+        command: header.command,
+        extra_headers: header.extra_headers,
+        point_indices: Vec::with_capacity(capacity),
+        data,
+    };
+    let shape = (plot.no_of_variables, plot.no_of_points, plot.flags);
+    plot.no_of_points = parse_values(
+        &mut plot.data,
+        &mut plot.point_indices,
+        shape,
+        &mut lines,
+        opts,
+        warnings,
+    )?;
+    Result::Ok(plot)
+}
+/// Yields the points of an ASCII `Values:` section one row at a time. Real
+/// plots give one value per variable, complex plots give interleaved
+/// `real, imaginary` pairs.
+pub struct PointIter<'a> {
+    lines: Peekable<Zip<RangeFrom<usize>, core::str::Lines<'a>>>,
+    no_of_variables: usize,
+    no_of_points: usize,
+    flags: Flags,
+    points_read: usize,
+    failed: bool,
+}
+impl PointIter<'_> {
+    fn next_point(
+        &mut self,
+        row: &mut Vec<f64>,
+        index: &mut Option<usize>,
+    ) -> Option<Result<(), SpiceParseError>> {
+        if self.failed {
+            return None;
+        }
+        match read_point(
+            &mut self.lines,
+            self.no_of_variables,
+            self.flags,
+            row,
+            index,
+            ParseOptions::default(),
+        ) {
+            Ok(Some(_)) => {
+                self.points_read += 1;
+                Some(Ok(()))
+            }
+            Ok(None) if self.points_read != self.no_of_points => {
+                self.failed = true;
+                Some(Err(SpiceParseError::NoOfPointMismatch))
+            }
+            Ok(None) => None,
+            Err(err) => {
+                self.failed = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
+impl Iterator for PointIter<'_> {
+    type Item = Result<Vec<f64>, SpiceParseError>;
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut row: Vec<f64> = Vec::with_capacity(row_len(self.no_of_variables, self.flags));
+        Some(self.next_point(&mut row, &mut None)?.map(|()| row))
+    }
+}
+pub fn parse_points(file: &str) -> Result<(PlotHeader, PointIter<'_>), SpiceParseError> {
+    let mut lines = (1..).zip(file.lines()).peekable();
+    let (header, _) = parse_header(&mut lines)?;
+    let header = header.into_owned();
+    let points = PointIter {
+        lines,
+        no_of_variables: header.no_of_variables,
+        no_of_points: header.no_of_points,
+        flags: header.flags,
+        points_read: 0,
+        failed: false,
+    };
+    Ok((header, points))
+}
+/// Folds `f` over the point rows of an ASCII rawfile, reusing one row buffer
+/// instead of building a [`Plot`]. Rows have the same layout as [`PointIter`].
+pub fn fold_points<B, F: FnMut(B, &[f64]) -> B>(
+    file: &str,
+    init: B,
+    mut f: F,
+) -> Result<B, SpiceParseError> {
+    let (header, mut points) = parse_points(file)?;
+    let mut row: Vec<f64> = Vec::with_capacity(row_len(header.no_of_variables, header.flags));
+    let mut acc = init;
+    while let Some(result) = points.next_point(&mut row, &mut None) {
+        result?;
+        acc = f(acc, &row);
+    }
+    Ok(acc)
+}
+const PROGRESS_INTERVAL: usize = 1024;
+/// Parses like [`parse`], calling `on_progress(points_parsed, no_of_points)`
+/// every 1024 points and once more when the values are read.
+pub fn parse_with_progress<F: FnMut(usize, usize)>(
+    file: &str,
+    mut on_progress: F,
+) -> Result<Plot, SpiceParseError> {
+    let (header, mut points) = parse_points(file)?;
+    let shape = (header.no_of_variables, header.no_of_points);
+    let mut plot = new_plot(header, ascii_capacity(shape, Some(file.len())));
+    let mut row: Vec<f64> = Vec::with_capacity(row_len(plot.no_of_variables, plot.flags));
+    let mut index: Option<usize> = None;
+    let mut points_parsed: usize = 0;
+    while let Some(result) = points.next_point(&mut row, &mut index) {
+        result?;
+        flush_values(&row, &mut plot.data, plot.flags, true);
+        push_index(&mut plot.point_indices, index);
+        points_parsed += 1;
+        if points_parsed.is_multiple_of(PROGRESS_INTERVAL) {
+            on_progress(points_parsed, plot.no_of_points);
+        }
+    }
+    if points_parsed == 0 || !points_parsed.is_multiple_of(PROGRESS_INTERVAL) {
+        on_progress(points_parsed, plot.no_of_points);
+    }
+    Ok(plot)
+}
+#[cfg(feature = "std")]
+struct ReaderLines<R: BufRead> {
+    lines: std::io::Lines<R>,
+    error: Option<std::io::Error>,
+}
+#[cfg(feature = "std")]
+impl<R: BufRead> Iterator for ReaderLines<R> {
+    type Item = String;
+    fn next(&mut self) -> Option<String> {
+        match self.lines.next()? {
+            Ok(lin) => Some(lin),
+            Err(err) => {
+                self.error = Some(err);
+                None
+            }
+        }
+    }
+}
+#[cfg(feature = "std")]
+pub fn parse_reader<R: Read>(reader: R) -> Result<Plot, SpiceParseError> {
+    let mut lines = ReaderLines {
+        lines: BufReader::new(reader).lines(),
+        error: None,
+    };
+    let plots = parse_all_lines(
+        (1..).zip(&mut lines),
+        None,
+        ParseOptions::default(),
+        &mut Vec::new(),
+    );
+    if let Some(err) = lines.error {
+        return Result::Err(SpiceParseError::Io(err));
+    }
+    let mut plots = plots?;
+    Result::Ok(plots.swap_remove(0))
+}
+fn find_section(bytes: &[u8]) -> Option<(Section, usize)> {
+    let mut start: usize = 0;
+    while start < bytes.len() {
+        let end = match bytes[start..].iter().position(|&b| b == b'\n') {
+            Some(pos) => start + pos,
+            None => bytes.len(),
+        };
+        let section = match bytes[start..end].trim_ascii() {
+            b"Values:" => Some(Section::Values),
+            b"Binary:" => Some(Section::Binary),
+            _ => None,
+        };
+        if let Some(section) = section {
+            return Some((section, (end + 1).min(bytes.len())));
+        }
+        start = end + 1;
+    }
+    None
+}
+fn read_f64_le(payload: &[u8], offset: &mut usize) -> Result<f64, SpiceParseError> {
+    let chunk: [u8; 8] = match payload.get(*offset..*offset + 8) {
+        Some(chunk) => chunk.try_into().unwrap(),
+        None => return Result::Err(SpiceParseError::TruncatedBinary),
+    };
+    *offset += 8;
+    Ok(f64::from_le_bytes(chunk))
+}
+fn binary_header(bytes: &[u8]) -> Result<(PlotHeader, &[u8]), SpiceParseError> {
+    let data_start = match find_section(bytes) {
+        Some((Section::Binary, data_start)) => data_start,
+        _ => return Result::Err(SpiceParseError::NoBinarySection),
+    };
+    let header = core::str::from_utf8(&bytes[..data_start])?;
+    let (header, section) = parse_header(&mut (1..).zip(header.lines()))?;
+    if !matches!(section, Section::Binary) {
+        return Result::Err(SpiceParseError::NoBinarySection);
+    }
+    Result::Ok((header.into_owned(), &bytes[data_start..]))
+}
+pub fn parse_binary(bytes: &[u8]) -> Result<Plot, SpiceParseError> {
+    let (header, payload) = binary_header(bytes)?;
+    let capacity = binary_capacity(&header, payload);
+    let mut plot = new_plot(header, capacity);
+    let mut offset: usize = 0;
+    let mut row: Vec<f64> = vec![0f64; row_len(plot.no_of_variables, plot.flags)];
+    for _ in 0..plot.no_of_points {
+        for val in row.iter_mut() {
+            *val = read_f64_le(payload, &mut offset)?;
+        }
+        flush_values(&row, &mut plot.data, plot.flags, true);
+        push_index(&mut plot.point_indices, None);
+    }
+    Result::Ok(plot)
+}
+/// `point_capacity` of a binary payload, which holds whole rows of `f64`s.
+fn binary_capacity(header: &PlotHeader, payload: &[u8]) -> usize {
+    let row_bytes = 8 * row_len(header.no_of_variables, header.flags).max(1);
+    point_capacity(header.no_of_points, Some(payload.len() / row_bytes))
+}
+pub fn parse_bytes(bytes: &[u8]) -> Result<Plot, SpiceParseError> {
+    match find_section(bytes) {
+        Some((Section::Binary, _)) => parse_binary(bytes),
+        _ => parse(core::str::from_utf8(bytes)?),
+    }
+}
+fn variable_column(
+    header: PlotHeader,
+    name: &str,
+    capacity: usize,
+) -> Result<(usize, VarData), SpiceParseError> {
+    let flags = header.flags;
+    let idx = header
+        .variables
+        .iter()
+        .position(|variable| variable.name == name)
+        .ok_or_else(|| SpiceParseError::VariableNotFound(String::from(name)))?;
+    let variable = header.variables.into_iter().nth(idx).unwrap();
+    Result::Ok((idx, new_var_data(variable, flags, capacity)))
+}
+/// Parses only the column of the variable `name`. The other values of each
+/// point pass through one reused row buffer, like in [`fold_points`].
+pub fn parse_variable(file: &str, name: &str) -> Result<VarData, SpiceParseError> {
+    let (header, mut points) = parse_points(file)?;
+    let flags = header.flags;
+    let mut row: Vec<f64> = Vec::with_capacity(row_len(header.no_of_variables, flags));
+    let shape = (header.no_of_variables, header.no_of_points);
+    let capacity = ascii_capacity(shape, Some(file.len()));
+    let (idx, mut var_data) = variable_column(header, name, capacity)?;
+    let width = row_len(1, flags);
+    while let Some(result) = points.next_point(&mut row, &mut None) {
+        result?;
+        flush_values(
+            &row[idx * width..(idx + 1) * width],
+            core::slice::from_mut(&mut var_data),
+            flags,
+            idx == 0,
+        );
+    }
+    Result::Ok(var_data)
+}
+/// Like [`parse_variable`] but also accepts binary rawfiles, reading only the
+/// bytes belonging to `name` from each point.
+pub fn parse_variable_bytes(bytes: &[u8], name: &str) -> Result<VarData, SpiceParseError> {
+    if !matches!(find_section(bytes), Some((Section::Binary, _))) {
+        return parse_variable(core::str::from_utf8(bytes)?, name);
+    }
+    let (header, payload) = binary_header(bytes)?;
+    let (flags, no_of_points) = (header.flags, header.no_of_points);
+    let stride = 8 * row_len(header.no_of_variables, flags);
+    let capacity = binary_capacity(&header, payload);
+    let (idx, mut var_data) = variable_column(header, name, capacity)?;
+    let width = row_len(1, flags);
+    let mut row: Vec<f64> = vec![0f64; width];
+    for point in 0..no_of_points {
+        let mut offset = point * stride + 8 * idx * width;
+        for val in row.iter_mut() {
+            *val = read_f64_le(payload, &mut offset)?;
+        }
+        flush_values(&row, core::slice::from_mut(&mut var_data), flags, idx == 0);
+    }
+    Result::Ok(var_data)
+}
+#[cfg(feature = "std")]
+pub fn parse_file<P: AsRef<Path>>(path: P) -> Result<Plot, SpiceParseError> {
+    let bytes = std::fs::read(path)?;
+    #[cfg(feature = "gzip")]
+    if bytes.starts_with(&[0x1f, 0x8b]) {
+        return parse_bytes(&gunzip(&bytes)?);
+    }
+    parse_bytes(&bytes)
+}
+#[cfg(feature = "gzip")]
+fn gunzip(bytes: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut ret: Vec<u8> = Vec::new();
+    flate2::read::GzDecoder::new(bytes).read_to_end(&mut ret)?;
+    Ok(ret)
+}
+/// Parses a gzip-compressed rawfile such as `out.raw.gz`.
+#[cfg(feature = "gzip")]
+pub fn parse_gz<P: AsRef<Path>>(path: P) -> Result<Plot, SpiceParseError> {
+    parse_bytes(&gunzip(&std::fs::read(path)?)?)
+}
+/// Memory-maps the file at `path` and parses it in place.
+///
+/// # Safety caveats
+///
+/// The mapping is only valid while the file is left alone: if another process
+/// truncates or rewrites it during parsing the result is undefined and may
+/// crash with `SIGBUS`. Only use this on rawfiles that are no longer being
+/// written by ngspice.
+#[cfg(feature = "mmap")]
+pub fn parse_mmap<P: AsRef<Path>>(path: P) -> Result<Plot, SpiceParseError> {
+    let file = std::fs::File::open(path)?;
+    let mmap = unsafe { memmap2::Mmap::map(&file)? };
+    parse_bytes(&mmap)
+}
+#[derive(Debug, Clone, Copy)]
+pub enum PhaseUnit {
+    Degrees,
+    Radians,
+}
+#[derive(Debug, Clone, Copy)]
+pub enum CsvLayout {
+    ColumnPerVariable,
+    RowPerVariable,
+}
+/// Which components of a complex variable go into the CSV.
+#[derive(Debug, Clone, Copy)]
+pub enum ComplexFormat {
+    Polar,
+    Rectangular,
+    Both,
+}
+impl ComplexFormat {
+    fn polar(self) -> bool {
+        !matches!(self, ComplexFormat::Rectangular)
+    }
+    fn rectangular(self) -> bool {
+        !matches!(self, ComplexFormat::Polar)
+    }
+    fn columns(self) -> usize {
+        2 * (self.polar() as usize + self.rectangular() as usize)
+    }
+}
+#[derive(Debug, Clone, Copy)]
+pub struct CsvOptions {
+    pub delimiter: char,
+    pub phase_unit: PhaseUnit,
+    pub layout: CsvLayout,
+    pub magnitude_db: bool,
+    pub complex_format: ComplexFormat,
+}
+impl Default for CsvOptions {
+    fn default() -> Self {
+        CsvOptions {
+            delimiter: ',',
+            phase_unit: PhaseUnit::Degrees,
+            layout: CsvLayout::ColumnPerVariable,
+            magnitude_db: false,
+            complex_format: ComplexFormat::Polar,
+        }
+    }
+}
+fn csv_field(field: &str, delimiter: char) -> String {
+    if field.contains(delimiter) || field.contains('"') {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        String::from(field)
+    }
+}
+fn csv_labels(var_data: &VarData, complex: bool, opts: CsvOptions) -> Vec<String> {
+    let name = match opts.magnitude_db && complex {
+        true => format!("{} - {}(dB)", var_data.name, var_data.typee),
+        false => format!("{} - {}", var_data.name, var_data.typee),
+    };
+    let mut labels: Vec<String> = Vec::new();
+    if !complex {
+        labels.push(name);
+    } else {
+        let unit = match opts.phase_unit {
+            PhaseUnit::Degrees => "deg",
+            PhaseUnit::Radians => "rad",
+        };
+        if opts.complex_format.polar() {
+            labels.push(name);
+            labels.push(format!("{}(phase,{})", var_data.typee, unit));
+        }
+        if opts.complex_format.rectangular() {
+            labels.push(format!("{} - {}(real)", var_data.name, var_data.typee));
+            labels.push(format!("{}(imag)", var_data.typee));
+        }
+    }
+    labels
+        .iter()
+        .map(|label| csv_field(label, opts.delimiter))
+        .collect()
+}
+fn csv_cells(
+    var_data: &VarData,
+    complex: bool,
+    i: usize,
+    opts: CsvOptions,
+    cells: &mut Vec<String>,
+) {
+    if !complex {
+        return cells.push(var_data.values[i].to_string());
+    }
+    let (magnitude, angle) = match (&var_data.angles, &var_data.real, &var_data.imag) {
+        (Some(angles), _, _) => (var_data.values[i], angles[i]),
+        (None, Some(real), Some(imag)) => to_polar(real[i], imag[i]),
+        _ => {
+            cells.extend((0..opts.complex_format.columns()).map(|_| String::new()));
+            return;
+        }
+    };
+    if opts.complex_format.polar() {
+        cells.push(csv_magnitude(magnitude, opts).to_string());
+        cells.push(csv_phase(angle, opts.phase_unit).to_string());
+    }
+    if opts.complex_format.rectangular() {
+        let real = var_data
+            .real
+            .as_ref()
+            .map_or_else(|| magnitude * math::cos(angle), |real| real[i]);
+        let imag = var_data
+            .imag
+            .as_ref()
+            .map_or_else(|| magnitude * math::sin(angle), |imag| imag[i]);
+        cells.push(real.to_string());
+        cells.push(imag.to_string());
+    }
+}
+fn csv_magnitude(magnitude: f64, opts: CsvOptions) -> f64 {
+    match opts.magnitude_db {
+        true => 20.0 * math::log10(magnitude),
+        false => magnitude,
+    }
+}
+fn csv_phase(angle: f64, unit: PhaseUnit) -> f64 {
+    match unit {
+        PhaseUnit::Degrees => angle.to_degrees(),
+        PhaseUnit::Radians => angle,
+    }
+}
+/// Non-finite values from diverging simulations are written as `inf`,
+/// `-inf` and `NaN`. A complex `inf,0` has magnitude `inf` and phase 0, and
+/// a NaN part makes both the magnitude and the phase NaN.
 pub fn parse_and_get_csv(file: &str) -> Result<String, SpiceParseError> {
+    parse_and_get_csv_with(file, CsvOptions::default())
+}
+pub fn parse_and_get_csv_with(file: &str, opts: CsvOptions) -> Result<String, SpiceParseError> {
+    let plot = parse(file)?;
     let mut ret = String::new();
+    let Result::Ok(()) = emit_csv(&plot, opts, |row| {
+        ret += row;
+        ret += "\n";
+        Result::<(), core::convert::Infallible>::Ok(())
+    });
+    Ok(ret)
+}
+#[cfg(feature = "std")]
+pub fn write_csv<W: Write>(plot: &Plot, mut writer: W, opts: CsvOptions) -> std::io::Result<()> {
+    emit_csv(plot, opts, |row| {
+        writer.write_all(row.as_bytes())?;
+        writer.write_all(b"\n")
+    })
+}
+fn emit_csv<E, F: FnMut(&str) -> Result<(), E>>(
+    plot: &Plot,
+    opts: CsvOptions,
+    mut emit: F,
+) -> Result<(), E> {
+    let delimiter = opts.delimiter.to_string();
+    let points = plot.actual_points();
+    let complex = matches!(plot.flags, Flags::Complex);
+    let mut cells: Vec<String> = Vec::new();
+    if let CsvLayout::RowPerVariable = opts.layout {
+        for var_data in plot.data.iter() {
+            let complex = complex && var_data.is_complex();
+            let mut rows: Vec<Vec<String>> = csv_labels(var_data, complex, opts)
+                .into_iter()
+                .map(|label| vec![label])
+                .collect();
+            for i in 0..points {
+                cells.clear();
+                csv_cells(var_data, complex, i, opts, &mut cells);
+                for (row, cell) in rows.iter_mut().zip(cells.drain(..)) {
+                    row.push(cell);
+                }
+            }
+            for row in rows.iter() {
+                emit(&row.join(&delimiter))?;
+            }
+        }
+        return Ok(());
+    }
+    let mut header: Vec<String> = Vec::new();
+    for var_data in plot.data.iter() {
+        header.extend(csv_labels(var_data, complex, opts));
+    }
+    emit(&header.join(&delimiter))?;
+    for i in 0..points {
+        cells.clear();
+        for var_data in plot.data.iter() {
+            csv_cells(var_data, complex, i, opts, &mut cells);
+        }
+        emit(&cells.join(&delimiter))?;
+    }
+    Ok(())
+}
+/// Quotes a gnuplot column title holding whitespace, so it stays one column.
+fn gnuplot_field(field: &str) -> String {
+    if field.contains(char::is_whitespace) || field.contains('"') {
+        format!("\"{}\"", field.replace('"', "\\\""))
+    } else {
+        String::from(field)
+    }
+}
+pub fn parse_and_get_gnuplot(file: &str) -> Result<String, SpiceParseError> {
     let plot = parse(file)?;
+    let mut columns: Vec<String> = Vec::new();
     for var_data in plot.data.iter() {
-        ret += var_data.name.as_str();
-        ret += " - ";
-        ret += var_data.typee.as_str();
-        ret += ",";
+        columns.push(gnuplot_field(&var_data.name));
         if let Flags::Complex = plot.flags {
-            ret += var_data.typee.as_str();
-            ret += "(phase),";
-        }
-    }
-    ret.remove(ret.len() - 1);
-    ret += "\n";
-    for i in 0..plot.no_of_points {
-        for j in 0..plot.no_of_variables {
-            let val: String = match plot.flags {
-                Flags::Real => plot.data[j].values[i].to_string(),
-                Flags::Complex => {
-                    if let Some(angles) = &plot.data[j].angles {
-                        let mut a = plot.data[j].values[i].to_string();
-                        a += ",";
-                        a += angles[i].to_degrees().to_string().as_str();
-                        a += "°";
-                        a
-                    } else {
-                        String::from("")
-                    }
-                }
-            };
-            ret += val.as_str();
-            ret += if j != (plot.no_of_variables - 1) {
-                ","
-            } else {
-                "\n"
-            };
+            columns.push(gnuplot_field(&format!("{}(phase,deg)", var_data.name)));
         }
     }
+    let mut ret = format!("# {}\n", columns.join(" "));
+    for i in 0..plot.actual_points() {
+        let mut row: Vec<String> = Vec::new();
+        for var_data in plot.data.iter() {
+            row.push(var_data.values[i].to_string());
+            if let Some(angles) = &var_data.angles {
+                row.push(angles[i].to_degrees().to_string());
+            }
+        }
+        ret += row.join(" ").as_str();
+        ret += "\n";
+    }
     Ok(ret)
 }
+#[cfg(feature = "json")]
+pub fn parse_and_get_json(file: &str) -> Result<String, SpiceParseError> {
+    let plot = parse(file)?;
+    Ok(serde_json::to_string_pretty(&plot)?)
+}
 #[cfg(test)]
 pub mod tests;
@@ -0,0 +1,22 @@
+#[cfg(feature = "std")]
+pub(crate) fn sqrt(x: f64) -> f64 {
+    x.sqrt()
+}
+#[cfg(feature = "std")]
+pub(crate) fn atan2(y: f64, x: f64) -> f64 {
+    y.atan2(x)
+}
+#[cfg(feature = "std")]
+pub(crate) fn sin(x: f64) -> f64 {
+    x.sin()
+}
+#[cfg(feature = "std")]
+pub(crate) fn log10(x: f64) -> f64 {
+    x.log10()
+}
+#[cfg(feature = "std")]
+pub(crate) fn cos(x: f64) -> f64 {
+    x.cos()
+}
+#[cfg(not(feature = "std"))]
+pub(crate) use libm::{atan2, cos, log10, sin, sqrt};
@@ -0,0 +1,130 @@
+use crate::{
+    flush_values, new_plot, parse_header, point_capacity, push_index, read_point, ParseOptions,
+    Plot, Section, SpiceParseError,
+};
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// Parses an ASCII rawfile that arrives in chunks. Lines split across chunks
+/// are buffered, and each point is stored as soon as all of its lines are in.
+/// Like [`crate::parse`], only the first plot is kept.
+#[derive(Debug, Default)]
+pub struct PlotParser {
+    pending: String,
+    line: usize,
+    header_lines: Vec<(usize, String)>,
+    point_lines: Vec<(usize, String)>,
+    row: Vec<f64>,
+    plot: Option<Plot>,
+    points_read: usize,
+    done: bool,
+    error: Option<SpiceParseError>,
+}
+impl PlotParser {
+    pub fn new() -> PlotParser {
+        PlotParser::default()
+    }
+    pub fn feed(&mut self, chunk: &str) {
+        self.pending.push_str(chunk);
+        while let Some(pos) = self.pending.find('\n') {
+            let lin: String = self.pending.drain(..=pos).collect();
+            self.push_line(lin.trim_end_matches(['\n', '\r']));
+        }
+    }
+    /// The plot holding the points read so far, once the header is complete.
+    pub fn plot(&self) -> Option<&Plot> {
+        self.plot.as_ref()
+    }
+    pub fn finish(mut self) -> Result<Plot, SpiceParseError> {
+        if !self.pending.is_empty() {
+            let lin = core::mem::take(&mut self.pending);
+            self.push_line(lin.trim_end_matches('\r'));
+        }
+        if self.plot.is_none() && self.error.is_none() {
+            self.start_plot();
+        }
+        if !self.point_lines.is_empty() && self.error.is_none() {
+            self.flush_point();
+        }
+        if let Some(err) = self.error {
+            return Result::Err(err);
+        }
+        let plot = self.plot.ok_or(SpiceParseError::NoOfPointMismatch)?;
+        if self.points_read != plot.no_of_points {
+            return Result::Err(SpiceParseError::NoOfPointMismatch);
+        }
+        Result::Ok(plot)
+    }
+    fn push_line(&mut self, lin: &str) {
+        self.line += 1;
+        if self.done || self.error.is_some() {
+            return;
+        }
+        let plot = match &self.plot {
+            Some(plot) => plot,
+            None => {
+                self.header_lines.push((self.line, String::from(lin)));
+                let key = lin.trim().split(':').next();
+                if matches!(key, Some("Values") | Some("Binary")) {
+                    self.start_plot();
+                }
+                return;
+            }
+        };
+        if lin.contains(':') {
+            self.done = true;
+            return;
+        }
+        if lin.trim().is_empty() {
+            return;
+        }
+        self.point_lines.push((self.line, String::from(lin)));
+        if self.point_lines.len() == plot.no_of_variables {
+            self.flush_point();
+        }
+    }
+    fn start_plot(&mut self) {
+        let mut lines = self
+            .header_lines
+            .iter()
+            .map(|(line, lin)| (*line, lin.as_str()));
+        match parse_header(&mut lines) {
+            Ok((_, Section::Binary)) => self.error = Some(SpiceParseError::NoBinarySection),
+            Ok((header, _)) => {
+                let capacity = point_capacity(header.no_of_points, None);
+                self.plot = Some(new_plot(header.into_owned(), capacity));
+            }
+            Err(err) => self.error = Some(err),
+        }
+    }
+    fn flush_point(&mut self) {
+        let plot = match self.plot.as_mut() {
+            Some(plot) => plot,
+            None => return,
+        };
+        let mut lines = self
+            .point_lines
+            .iter()
+            .map(|(line, lin)| (*line, lin.as_str()))
+            .peekable();
+        let mut index: Option<usize> = None;
+        let result = read_point(
+            &mut lines,
+            plot.no_of_variables,
+            plot.flags,
+            &mut self.row,
+            &mut index,
+            ParseOptions::default(),
+        );
+        self.point_lines.clear();
+        match result {
+            Ok(None) => {}
+            Ok(Some(_)) => {
+                flush_values(&self.row, &mut plot.data, plot.flags, true);
+                push_index(&mut plot.point_indices, index);
+                self.points_read += 1;
+            }
+            Err(err) => self.error = Some(err),
+        }
+    }
+}
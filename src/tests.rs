@@ -1,4 +1,8 @@
-use crate::{parse, parse_and_get_csv};
+use crate::{
+    parse, parse_all, parse_and_get_csv, parse_and_get_csv_with, parse_and_get_gnuplot,
+    parse_binary, parse_borrowed, parse_bytes, parse_points, parse_with, CsvOptions, Flags,
+    ParseOptions, PhaseUnit, SpiceParseError,
+};
 
 #[test]
 fn operating_point_sample_real() {
@@ -21,9 +25,7 @@ Values:
     println!("{:#?}", parse(file).unwrap());
 }
 
-#[test]
-fn ac_analysis_sample_complex() {
-    let file = "Title: rc circuit frequency response
+const AC_ANALYSIS_SAMPLE: &str = "Title: rc circuit frequency response
 Date: Sat Dec 25 20:19:58  2021
 Plotname: AC Analysis
 Flags: complex
@@ -86,6 +88,2212 @@ Values:
     -4.969460978668499e-06,-9.957020847080085e-05
 
 ";
+
+#[test]
+fn ac_analysis_sample_complex() {
+    let file = AC_ANALYSIS_SAMPLE;
     println!("{:#?}", parse(file).unwrap());
     println!("{}", parse_and_get_csv(file).unwrap());
 }
+
+fn binary_sample(values: &[f64]) -> Vec<u8> {
+    let mut file = Vec::from(
+        "Title: basic r circuit
+Date: Sat Dec 25 20:36:08  2021
+Plotname: Operating Point
+Flags: real
+No. Variables: 3
+No. Points: 2
+Variables:
+\t0\tv(a)\tvoltage
+\t1\tv(b)\tvoltage
+\t2\ti(v1)\tcurrent
+Binary:
+"
+        .as_bytes(),
+    );
+    for val in values {
+        file.extend_from_slice(&val.to_le_bytes());
+    }
+    file
+}
+
+#[test]
+fn operating_point_sample_binary() {
+    let file = binary_sample(&[100.0, 30.0, -1.0, 50.0, 15.0, -0.5]);
+    let plot = parse_binary(&file).unwrap();
+    assert_eq!(plot.data.len(), 3);
+    assert_eq!(plot.data[0].values, vec![100.0, 50.0]);
+    assert_eq!(plot.data[1].values, vec![30.0, 15.0]);
+    assert_eq!(plot.data[2].values, vec![-1.0, -0.5]);
+}
+
+#[test]
+fn truncated_binary_sample() {
+    let file = binary_sample(&[100.0, 30.0, -1.0, 50.0]);
+    assert!(matches!(
+        parse_binary(&file),
+        Err(SpiceParseError::TruncatedBinary)
+    ));
+}
+
+#[test]
+fn bytes_dispatch_ascii_and_binary() {
+    let ascii = "Title: basic r circuit
+Plotname: Operating Point
+Flags: real
+No. Variables: 3
+No. Points: 1
+Variables:
+\t0\tv(a)\tvoltage
+\t1\tv(b)\tvoltage
+\t2\ti(v1)\tcurrent
+Values:
+ 0\t1.000000000000000e+02
+\t3.000000000000000e+01
+\t-1.000000000000000e+00
+";
+    let from_ascii = parse_bytes(ascii.as_bytes()).unwrap();
+    let from_binary = parse_bytes(&binary_sample(&[100.0, 30.0, -1.0, 50.0, 15.0, -0.5])).unwrap();
+    assert_eq!(from_ascii.data[0].values, vec![100.0]);
+    assert_eq!(from_binary.data[0].values, vec![100.0, 50.0]);
+}
+
+#[test]
+fn bytes_invalid_utf8_in_values() {
+    let mut file = Vec::from(
+        "Flags: real
+No. Variables: 1
+No. Points: 1
+Variables:
+\t0\tv(a)\tvoltage
+Values:
+ 0\t"
+        .as_bytes(),
+    );
+    file.extend_from_slice(&[0xff, 0xfe]);
+    assert!(matches!(parse_bytes(&file), Err(SpiceParseError::Utf8(_))));
+}
+
+#[test]
+fn multiple_plots_in_one_file() {
+    let file = "Title: basic r circuit
+Date: Sat Dec 25 20:36:08  2021
+Plotname: Operating Point
+Flags: real
+No. Variables: 2
+No. Points: 1
+Variables:
+\t0\tv(a)\tvoltage
+\t1\ti(v1)\tcurrent
+Values:
+ 0\t1.000000000000000e+02
+\t-1.000000000000000e+00
+
+Title: basic r circuit
+Date: Sat Dec 25 20:36:08  2021
+Plotname: Transient Analysis
+Flags: real
+No. Variables: 2
+No. Points: 2
+Variables:
+\t0\ttime\ttime
+\t1\tv(a)\tvoltage
+Values:
+ 0\t0.000000000000000e+00
+\t0.000000000000000e+00
+ 1\t1.000000000000000e-03
+\t5.000000000000000e+01
+";
+    let plots = parse_all(file).unwrap();
+    assert_eq!(plots.len(), 2);
+    assert_eq!(plots[0].plotname, "Operating Point");
+    assert_eq!(plots[0].data[1].values, vec![-1.0]);
+    assert_eq!(plots[1].plotname, "Transient Analysis");
+    assert_eq!(plots[1].data[0].values, vec![0.0, 1e-3]);
+    assert_eq!(plots[1].data[1].values, vec![0.0, 50.0]);
+    assert_eq!(parse(file).unwrap().plotname, "Operating Point");
+}
+
+#[test]
+fn complex_keeps_rectangular_components() {
+    let file = "Title: rc circuit frequency response
+Plotname: AC Analysis
+Flags: complex
+No. Variables: 2
+No. Points: 2
+Variables:
+\t0\tfrequency\tfrequency grid=3
+\t1\tv(2)\tvoltage
+Values:
+ 0\t1.000000000000000e+00,0.000000000000000e+00
+\t3.000000000000000e+00,4.000000000000000e+00
+ 1\t2.000000000000000e+00,0.000000000000000e+00
+\t1.999921046281759e+00,-1.256587453351677e-02
+";
+    let plot = parse(file).unwrap();
+    let var = &plot.data[1];
+    assert_eq!(var.values[0], 5.0);
+    assert_eq!(var.real, Some(vec![3.0, 1.999921046281759]));
+    assert_eq!(var.imag, Some(vec![4.0, -1.256587453351677e-02]));
+}
+
+#[test]
+fn complex_phase_in_all_quadrants() {
+    let file = "Title: quadrants
+Plotname: AC Analysis
+Flags: complex
+No. Variables: 1
+No. Points: 4
+Variables:
+\t0\tv(1)\tvoltage
+Values:
+ 0\t1.0,1.0
+ 1\t-1.0,1.0
+ 2\t-1.0,-1.0
+ 3\t1.0,-1.0
+";
+    let plot = parse(file).unwrap();
+    let angles: Vec<f64> = plot.data[0]
+        .angles
+        .as_ref()
+        .unwrap()
+        .iter()
+        .map(|a| a.to_degrees())
+        .collect();
+    let expected = [45.0, 135.0, -135.0, -45.0];
+    for (angle, expected) in angles.iter().zip(expected) {
+        assert!((angle - expected).abs() < 1e-9, "{} != {}", angle, expected);
+    }
+}
+
+#[test]
+fn complex_phase_with_zero_real_part() {
+    let file = "Title: zero real part
+Plotname: AC Analysis
+Flags: complex
+No. Variables: 1
+No. Points: 2
+Variables:
+\t0\tv(1)\tvoltage
+Values:
+ 0\t0.000000000000000e+00,0.000000000000000e+00
+ 1\t0.000000000000000e+00,5.000000000000000e+00
+";
+    let plot = parse(file).unwrap();
+    let angles = plot.data[0].angles.as_ref().unwrap();
+    assert_eq!(plot.data[0].values, vec![0.0, 5.0]);
+    assert_eq!(angles[0], 0.0);
+    assert_eq!(angles[1], std::f64::consts::FRAC_PI_2);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn parse_from_reader() {
+    let file = "Title: basic r circuit
+Plotname: Operating Point
+Flags: real
+No. Variables: 2
+No. Points: 1
+Variables:
+\t0\tv(a)\tvoltage
+\t1\ti(v1)\tcurrent
+Values:
+ 0\t1.000000000000000e+02
+\t-1.000000000000000e+00
+";
+    let plot = crate::parse_reader(file.as_bytes()).unwrap();
+    assert_eq!(plot.data[0].values, vec![100.0]);
+    assert_eq!(plot.data[1].values, vec![-1.0]);
+    assert!(matches!(
+        crate::parse_reader(&[b'T', 0xff, b'\n'][..]),
+        Err(SpiceParseError::Io(_))
+    ));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn parse_from_file() {
+    let path = std::env::temp_dir().join("ngspice-parser-parse-from-file.raw");
+    std::fs::write(&path, binary_sample(&[100.0, 30.0, -1.0, 50.0, 15.0, -0.5])).unwrap();
+    let plot = crate::parse_file(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(plot.data[2].values, vec![-1.0, -0.5]);
+    assert!(matches!(
+        crate::parse_file(std::env::temp_dir().join("ngspice-parser-missing.raw")),
+        Err(SpiceParseError::Io(_))
+    ));
+}
+
+#[test]
+fn stream_points_without_building_plot() {
+    let file = "Title: rc circuit
+Plotname: Transient Analysis
+Flags: real
+No. Variables: 2
+No. Points: 3
+Variables:
+\t0\ttime\ttime
+\t1\tv(a)\tvoltage
+Values:
+ 0\t0.000000000000000e+00
+\t1.000000000000000e+00
+ 1\t1.000000000000000e-03
+\t2.000000000000000e+00
+ 2\t2.000000000000000e-03
+\t3.000000000000000e+00
+";
+    let (header, points) = parse_points(file).unwrap();
+    assert_eq!(header.variables[1].name, "v(a)");
+    let rows: Vec<Vec<f64>> = points.collect::<Result<_, _>>().unwrap();
+    assert_eq!(rows.len(), 3);
+    assert_eq!(rows[2], vec![2e-3, 3.0]);
+}
+
+#[test]
+fn lookup_variable_by_name() {
+    let file = "Title: basic r circuit
+Plotname: Operating Point
+Flags: real
+No. Variables: 2
+No. Points: 1
+Variables:
+\t0\tV(a)\tvoltage
+\t1\ti(v1)\tcurrent
+Values:
+ 0\t1.000000000000000e+02
+\t-1.000000000000000e+00
+";
+    let plot = parse(file).unwrap();
+    assert_eq!(plot.variable("i(v1)").unwrap().values, vec![-1.0]);
+    assert_eq!(plot.variable_index("i(v1)"), Some(1));
+    assert!(plot.variable("v(a)").is_none());
+    assert_eq!(plot.variable_ci("v(a)").unwrap().name, "V(a)");
+}
+
+#[cfg(feature = "num-complex")]
+#[test]
+fn complex_values_from_rectangular() {
+    let file = "Title: rc circuit frequency response
+Plotname: AC Analysis
+Flags: complex
+No. Variables: 1
+No. Points: 2
+Variables:
+\t0\tv(1)\tvoltage
+Values:
+ 0\t3.0,4.0
+ 1\t-1.0,0.5
+";
+    let plot = parse(file).unwrap();
+    let values = plot.data[0].complex_values().unwrap();
+    assert_eq!(values[0], num_complex::Complex::new(3.0, 4.0));
+    assert_eq!(values[1], num_complex::Complex::new(-1.0, 0.5));
+}
+
+#[test]
+fn csv_with_custom_delimiter_and_quoting() {
+    let file = "Title: basic r circuit
+Plotname: Operating Point
+Flags: real
+No. Variables: 2
+No. Points: 1
+Variables:
+\t0\tv(a,b)\tvoltage
+\t1\ti(v1)\tcurrent
+Values:
+ 0\t1.000000000000000e+02
+\t-1.000000000000000e+00
+";
+    assert_eq!(
+        parse_and_get_csv(file).unwrap(),
+        "\"v(a,b) - voltage\",i(v1) - current\n100,-1\n"
+    );
+    assert_eq!(
+        parse_and_get_csv_with(
+            file,
+            CsvOptions {
+                delimiter: ';',
+                ..CsvOptions::default()
+            }
+        )
+        .unwrap(),
+        "v(a,b) - voltage;i(v1) - current\n100;-1\n"
+    );
+}
+
+#[test]
+fn csv_phase_in_radians() {
+    let file = "Title: rc circuit
+Plotname: AC Analysis
+Flags: complex
+No. Variables: 1
+No. Points: 1
+Variables:
+\t0\tv(1)\tvoltage
+Values:
+ 0\t0.0,2.0
+";
+    let csv = parse_and_get_csv_with(
+        file,
+        CsvOptions {
+            phase_unit: PhaseUnit::Radians,
+            ..CsvOptions::default()
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        csv.lines().nth(1).unwrap(),
+        format!("2,{}", std::f64::consts::FRAC_PI_2)
+    );
+}
+
+#[test]
+fn csv_is_plain_ascii() {
+    let file = "Title: rc circuit
+Plotname: AC Analysis
+Flags: complex
+No. Variables: 2
+No. Points: 1
+Variables:
+\t0\tfrequency\tfrequency
+\t1\tv(1)\tvoltage
+Values:
+ 0\t1.0,0.0
+\t1.0,1.0
+";
+    let csv = parse_and_get_csv(file).unwrap();
+    assert!(csv.is_ascii());
+    assert_eq!(
+        csv,
+        "frequency - frequency,\"frequency(phase,deg)\",v(1) - voltage,\"voltage(phase,deg)\"
+1,0,1.4142135623730951,45
+"
+    );
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn json_output() {
+    let file = "Title: basic r circuit
+Plotname: Operating Point
+Flags: real
+No. Variables: 1
+No. Points: 1
+Variables:
+\t0\tv(a)\tvoltage
+Values:
+ 0\t1.000000000000000e+02
+";
+    let json: serde_json::Value =
+        serde_json::from_str(&crate::parse_and_get_json(file).unwrap()).unwrap();
+    assert_eq!(json["plotname"], "Operating Point");
+    assert_eq!(json["data"][0]["name"], "v(a)");
+    assert_eq!(json["data"][0]["values"][0], 100.0);
+}
+
+#[test]
+fn rawfile_round_trip() {
+    let plot = parse(AC_ANALYSIS_SAMPLE).unwrap();
+    assert_eq!(parse(&plot.to_rawfile()).unwrap(), plot);
+    let plot = parse_binary(&binary_sample(&[100.0, 30.0, -1.0, 50.0, 15.0, -0.5])).unwrap();
+    let rawfile = plot.to_rawfile();
+    assert!(rawfile.contains("\t0\tv(a)\tvoltage\n"));
+    assert!(rawfile.contains(" 1\t5.000000000000000e+01\n"));
+    assert_eq!(parse(&rawfile).unwrap(), plot);
+}
+
+#[test]
+fn rawfile_of_short_column() {
+    let mut plot = parse(AC_ANALYSIS_SAMPLE).unwrap();
+    let points = plot.no_of_points;
+    plot.data[1].values.pop();
+    let reparsed = parse(&plot.to_rawfile()).unwrap();
+    assert_eq!(reparsed.no_of_points, points - 1);
+    assert_eq!(reparsed.data[0].values, plot.data[0].values[..points - 1]);
+}
+
+const TWO_PORT_SAMPLE: &str = "Title: two port
+Plotname: AC Analysis
+Flags: complex
+No. Variables: 5
+No. Points: 1
+Variables:
+\t0\tfrequency\tfrequency
+\t1\ts11\tvoltage
+\t2\ts21\tvoltage
+\t3\ts12\tvoltage
+\t4\ts22\tvoltage
+Values:
+ 0\t1000.0,0.0
+\t0.5,0.0
+\t0.0,2.0
+\t0.0,-2.0
+\t-0.5,0.0
+";
+
+#[test]
+fn touchstone_two_port() {
+    let plot = parse(TWO_PORT_SAMPLE).unwrap();
+    assert_eq!(
+        plot.to_touchstone(2).unwrap(),
+        "# Hz S MA R 50\n1000 0.5 0 2 90 2 -90 0.5 180\n"
+    );
+    assert!(matches!(
+        plot.to_touchstone(1),
+        Err(SpiceParseError::PortMismatch { expected: 2 })
+    ));
+    let real = parse_binary(&binary_sample(&[100.0, 30.0, -1.0, 50.0, 15.0, -0.5])).unwrap();
+    assert!(matches!(
+        real.to_touchstone(1),
+        Err(SpiceParseError::NotComplex)
+    ));
+    let mut short = parse(TWO_PORT_SAMPLE).unwrap();
+    short.data[2].values.clear();
+    assert_eq!(short.to_touchstone(2).unwrap(), "# Hz S MA R 50\n");
+}
+
+#[test]
+fn touchstone_wraps_rows_of_five_ports() {
+    let mut file = String::from(
+        "Title: five ports\nPlotname: AC Analysis\nFlags: complex\nNo. Variables: 26\nNo. Points: 1\nVariables:\n\t0\tfrequency\tfrequency\n",
+    );
+    for idx in 0..25 {
+        file += format!("\t{}\ts{}\tvoltage\n", idx + 1, idx).as_str();
+    }
+    file += "Values:\n 0\t1000.0,0.0\n";
+    for idx in 0..25 {
+        file += format!("\t{}.0,0.0\n", idx).as_str();
+    }
+    let touchstone = parse(&file).unwrap().to_touchstone(5).unwrap();
+    let lines: Vec<&str> = touchstone.lines().collect();
+    assert_eq!(lines.len(), 1 + 2 * 5);
+    assert_eq!(lines[1], "1000 0 0 1 0 2 0 3 0");
+    assert_eq!(lines[2], " 4 0");
+    assert_eq!(lines[3], " 5 0 6 0 7 0 8 0");
+    assert_eq!(lines[10], " 24 0");
+}
+
+#[test]
+fn errors_report_line_numbers() {
+    let file = "Title: basic r circuit
+Plotname: Operating Point
+Flags: real
+No. Variables: 2
+No. Points: 2
+Variables:
+\t0\tv(a)\tvoltage
+\t1\ti(v1)\tcurrent
+Values:
+ 0\t1.000000000000000e+02
+\t-1.000000000000000e+00
+ 1\t1.000000000000000e+02
+";
+    let err = parse(file).unwrap_err();
+    assert!(matches!(err, SpiceParseError::At { line: 12, .. }));
+    assert_eq!(err.to_string(), "Number of values mismatch at line 12");
+}
+
+#[test]
+fn extra_tokens_on_a_value_line() {
+    let file = TRANSIENT_SAMPLE.replace(" 1\t1.000000000000000e-03", " 1\t1\t5");
+    assert!(matches!(
+        parse(&file),
+        Err(SpiceParseError::At { line: 13, source }) if matches!(*source, SpiceParseError::NoOfValMismatch)
+    ));
+    let spaced = AC_ANALYSIS_SAMPLE.replace(",", ", ");
+    assert_eq!(parse(&spaced).unwrap(), parse(AC_ANALYSIS_SAMPLE).unwrap());
+}
+
+#[test]
+fn crlf_line_endings() {
+    let crlf = AC_ANALYSIS_SAMPLE.replace('\n', "\r\n");
+    assert_eq!(parse(&crlf).unwrap(), parse(AC_ANALYSIS_SAMPLE).unwrap());
+    let binary = binary_sample(&[100.0, 30.0, -1.0, 50.0, 15.0, -0.5]);
+    let split = binary.len() - 6 * 8;
+    let mut crlf_binary: Vec<u8> = Vec::new();
+    for &b in &binary[..split] {
+        if b == b'\n' {
+            crlf_binary.push(b'\r');
+        }
+        crlf_binary.push(b);
+    }
+    crlf_binary.extend_from_slice(&binary[split..]);
+    assert_eq!(
+        parse_binary(&crlf_binary).unwrap(),
+        parse_binary(&binary).unwrap()
+    );
+}
+
+#[test]
+fn leading_byte_order_mark() {
+    let file = format!("\u{FEFF}{}", AC_ANALYSIS_SAMPLE);
+    let plot = parse(&file).unwrap();
+    assert_eq!(plot.title, "rc circuit frequency response");
+    assert_eq!(parse_bytes(file.as_bytes()).unwrap(), plot);
+}
+
+#[test]
+fn space_separated_columns() {
+    let file = "Title: basic r circuit
+Plotname: Operating Point
+Flags: real
+No. Variables: 2
+No. Points: 2
+Variables:
+  0  v(a)  voltage
+  1 \t i(v1)   current
+Values:
+ 0 1.000000000000000e+02
+   -1.000000000000000e+00
+ 1    5.000000000000000e+01
+  \t -5.000000000000000e-01
+";
+    let plot = parse(file).unwrap();
+    assert_eq!(plot.data[1].name, "i(v1)");
+    assert_eq!(plot.data[1].typee, "current");
+    assert_eq!(plot.data[0].values, vec![100.0, 50.0]);
+    assert_eq!(plot.data[1].values, vec![-1.0, -0.5]);
+}
+
+#[test]
+fn truncated_values_report_point_mismatch() {
+    let file = "Title: basic r circuit
+Plotname: Operating Point
+Flags: real
+No. Variables: 2
+No. Points: 3
+Variables:
+\t0\tv(a)\tvoltage
+\t1\ti(v1)\tcurrent
+Values:
+ 0\t1.000000000000000e+02
+\t-1.000000000000000e+00
+ 1\t1.000000000000000e+02
+\t-1.000000000000000e+00
+";
+    assert!(matches!(
+        parse(file),
+        Err(SpiceParseError::NoOfPointMismatch)
+    ));
+    let (_, points) = parse_points(file).unwrap();
+    assert!(matches!(
+        points.last(),
+        Some(Err(SpiceParseError::NoOfPointMismatch))
+    ));
+}
+
+#[test]
+fn command_header() {
+    let file = "Title: basic r circuit
+Plotname: Operating Point
+Flags: real
+No. Variables: 1
+No. Points: 1
+Command: version 42
+Variables:
+\t0\tv(a)\tvoltage
+Values:
+ 0\t1.000000000000000e+02
+";
+    let plot = parse(file).unwrap();
+    assert_eq!(plot.command.as_deref(), Some("version 42"));
+    assert_eq!(parse(&plot.to_rawfile()).unwrap(), plot);
+    assert_eq!(parse(AC_ANALYSIS_SAMPLE).unwrap().command, None);
+}
+
+#[test]
+fn unknown_headers_are_kept() {
+    let file = "Title: basic r circuit
+Plotname: Operating Point
+Flags: real
+No. Variables: 1
+No. Points: 1
+Option: temp=27 tnom=27
+Vendor: some:thing
+Variables:
+\t0\tv(a)\tvoltage
+Values:
+ 0\t1.000000000000000e+02
+";
+    let plot = parse(file).unwrap();
+    assert_eq!(
+        plot.extra_headers,
+        vec![
+            (String::from("Option"), String::from("temp=27 tnom=27")),
+            (String::from("Vendor"), String::from("some:thing")),
+        ]
+    );
+    assert_eq!(parse(&plot.to_rawfile()).unwrap(), plot);
+}
+
+#[test]
+fn dimensions_header() {
+    let file = "Title: nested sweep
+Plotname: DC transfer characteristic
+Flags: real
+No. Variables: 1
+No. Points: 4
+Dimensions: 2,2
+Variables:
+\t0\tv(a)\tvoltage
+Values:
+ 0\t1.0
+ 1\t2.0
+ 2\t3.0
+ 3\t4.0
+";
+    let plot = parse(file).unwrap();
+    assert_eq!(plot.dimensions, Some(vec![2, 2]));
+    assert_eq!(parse(&plot.to_rawfile()).unwrap(), plot);
+    let bad = file.replace("Dimensions: 2,2", "Dimensions: 3,2");
+    let err = parse(&bad).unwrap_err();
+    assert!(matches!(err, SpiceParseError::At { line: 6, .. }));
+    assert_eq!(
+        err.to_string(),
+        "Dimensions do not match number of points at line 6"
+    );
+    let overflow = file.replace("Dimensions: 2,2", "Dimensions: 99999999999,99999999999");
+    assert!(matches!(
+        parse(&overflow),
+        Err(SpiceParseError::At { line: 6, source }) if matches!(*source, SpiceParseError::DimensionsMismatch)
+    ));
+}
+
+#[test]
+fn scale_variable() {
+    let plot = parse(AC_ANALYSIS_SAMPLE).unwrap();
+    assert_eq!(plot.scale_index(), 0);
+    assert_eq!(plot.scale().name, "frequency");
+    assert_eq!(plot.scale().values.len(), 10);
+}
+
+#[test]
+fn filter_variables_by_type() {
+    let plot = parse(AC_ANALYSIS_SAMPLE).unwrap();
+    let voltages: Vec<&str> = plot
+        .variables_of_type("voltage")
+        .iter()
+        .map(|var_data| var_data.name.as_str())
+        .collect();
+    assert_eq!(voltages, vec!["v(1)", "v(2)"]);
+    assert_eq!(plot.variables_of_type("current").len(), 1);
+    assert!(plot.variables_of_type("Voltage").is_empty());
+}
+
+#[test]
+fn huge_point_count_is_not_preallocated() {
+    let huge = "No. Points: 100000000000000";
+    let file = AC_ANALYSIS_SAMPLE.replace("No. Points: 10", huge);
+    let mismatch = |result: Result<(), SpiceParseError>| {
+        matches!(result, Err(SpiceParseError::NoOfPointMismatch))
+    };
+    assert!(mismatch(parse(&file).map(|_| ())));
+    assert!(mismatch(parse_borrowed(&file).map(|_| ())));
+    assert!(mismatch(crate::parse_variable(&file, "v(1)").map(|_| ())));
+    assert!(mismatch(
+        crate::parse_with_progress(&file, |_, _| ()).map(|_| ())
+    ));
+    let mut parser = crate::PlotParser::new();
+    parser.feed(&file);
+    assert!(mismatch(parser.finish().map(|_| ())));
+    #[cfg(feature = "std")]
+    assert!(mismatch(crate::parse_reader(file.as_bytes()).map(|_| ())));
+    let mut binary = binary_sample(&[100.0, 30.0, -1.0, 50.0, 15.0, -0.5]);
+    let header_end = binary.len() - 6 * 8;
+    let header = String::from_utf8(binary[..header_end].to_vec()).unwrap();
+    binary.splice(
+        ..header_end,
+        header.replace("No. Points: 2", huge).into_bytes(),
+    );
+    assert!(matches!(
+        parse_binary(&binary),
+        Err(SpiceParseError::TruncatedBinary)
+    ));
+    assert!(matches!(
+        crate::parse_variable_bytes(&binary, "v(b)"),
+        Err(SpiceParseError::TruncatedBinary)
+    ));
+}
+
+#[cfg(feature = "mmap")]
+#[test]
+fn parse_from_mmap() {
+    let path = std::env::temp_dir().join("ngspice-parser-parse-from-mmap.raw");
+    std::fs::write(&path, binary_sample(&[100.0, 30.0, -1.0, 50.0, 15.0, -0.5])).unwrap();
+    let plot = crate::parse_mmap(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(plot.data[1].values, vec![30.0, 15.0]);
+}
+
+#[test]
+fn borrowed_parse_matches_owned() {
+    let owned = parse(AC_ANALYSIS_SAMPLE).unwrap();
+    let borrowed = parse_borrowed(AC_ANALYSIS_SAMPLE).unwrap();
+    assert_eq!(borrowed.title, owned.title);
+    assert_eq!(borrowed.date, owned.date);
+    assert_eq!(borrowed.no_of_points, owned.no_of_points);
+    for (var_ref, var_data) in borrowed.data.iter().zip(owned.data.iter()) {
+        assert_eq!(var_ref.name, var_data.name);
+        assert_eq!(var_ref.typee, var_data.typee);
+        assert_eq!(var_ref.values, var_data.values);
+        assert_eq!(var_ref.angles, var_data.angles);
+    }
+}
+
+#[test]
+fn flags_are_case_insensitive() {
+    let upper = AC_ANALYSIS_SAMPLE.replace("Flags: complex", "Flags: COMPLEX");
+    assert_eq!(parse(&upper).unwrap(), parse(AC_ANALYSIS_SAMPLE).unwrap());
+    let mixed = AC_ANALYSIS_SAMPLE.replace("Flags: complex", "Flags: Complex");
+    assert_eq!(parse(&mixed).unwrap().flags, Flags::Complex);
+}
+
+#[test]
+fn flags_with_modifiers() {
+    let file = AC_ANALYSIS_SAMPLE.replace("Flags: complex", "Flags: complex stale");
+    let plot = parse(&file).unwrap();
+    assert_eq!(plot.flags, Flags::Complex);
+    assert_eq!(plot.flag_modifiers, vec![String::from("stale")]);
+    assert_eq!(parse(&plot.to_rawfile()).unwrap(), plot);
+    let plot = parse(AC_ANALYSIS_SAMPLE).unwrap();
+    assert!(plot.flag_modifiers.is_empty());
+}
+
+#[test]
+fn unknown_flags_are_not_errors() {
+    let file = AC_ANALYSIS_SAMPLE.replace("Flags: complex", "Flags: complex shiny");
+    let plot = parse(&file).unwrap();
+    assert_eq!(plot.flags, Flags::Complex);
+    assert_eq!(plot.flag_modifiers, vec![String::from("shiny")]);
+    let file = "Title: basic r circuit
+Plotname: Operating Point
+Flags: shiny
+No. Variables: 1
+No. Points: 1
+Variables:
+\t0\tv(a)\tvoltage
+Values:
+ 0\t1.000000000000000e+02
+";
+    let plot = parse(file).unwrap();
+    assert_eq!(plot.flags, Flags::Real);
+    assert_eq!(plot.data[0].values, vec![100.0]);
+}
+
+#[test]
+fn gnuplot_output() {
+    let file = "Title: rc circuit
+Plotname: AC Analysis
+Flags: complex
+No. Variables: 2
+No. Points: 2
+Variables:
+\t0\tfrequency\tfrequency
+\t1\tv(1)\tvoltage
+Values:
+ 0\t1.0,0.0
+\t0.0,2.0
+ 1\t2.0,0.0
+\t-3.0,0.0
+";
+    assert_eq!(
+        parse_and_get_gnuplot(file).unwrap(),
+        "# frequency frequency(phase,deg) v(1) v(1)(phase,deg)
+1 0 2 90
+2 0 3 180
+"
+    );
+}
+
+#[cfg(feature = "npy")]
+fn read_npy(bytes: &[u8]) -> (String, Vec<f64>) {
+    assert_eq!(&bytes[..8], b"\x93NUMPY\x01\x00");
+    let header_len = u16::from_le_bytes([bytes[8], bytes[9]]) as usize;
+    assert_eq!((10 + header_len) % 64, 0);
+    let header = String::from_utf8(bytes[10..10 + header_len].to_vec()).unwrap();
+    let values = bytes[10 + header_len..]
+        .chunks(8)
+        .map(|chunk| f64::from_le_bytes(chunk.try_into().unwrap()))
+        .collect();
+    (header, values)
+}
+
+#[cfg(feature = "npy")]
+#[test]
+fn npy_round_trip() {
+    let plot = parse_binary(&binary_sample(&[100.0, 30.0, -1.0, 50.0, 15.0, -0.5])).unwrap();
+    let (header, values) = read_npy(&plot.to_npy().unwrap());
+    assert!(header.starts_with("{'descr': '<f8', 'fortran_order': False, 'shape': (2, 3), }"));
+    assert!(header.ends_with('\n'));
+    assert_eq!(values, vec![100.0, 30.0, -1.0, 50.0, 15.0, -0.5]);
+    let plot = parse(AC_ANALYSIS_SAMPLE).unwrap();
+    let (header, values) = read_npy(&plot.to_npy().unwrap());
+    assert!(header.contains("'descr': '<c16'"));
+    assert!(header.contains("'shape': (10, 4)"));
+    assert_eq!(values.len(), 10 * 4 * 2);
+    assert_eq!(values[4..6], [1.999921046281759, -1.256587453351677e-02]);
+}
+
+#[cfg(feature = "arrow")]
+#[test]
+fn arrow_record_batch() {
+    let plot = parse(AC_ANALYSIS_SAMPLE).unwrap();
+    let batch = plot.to_record_batch().unwrap();
+    assert_eq!(batch.num_rows(), 10);
+    assert_eq!(batch.num_columns(), 8);
+    let schema = batch.schema();
+    assert_eq!(schema.field(0).name(), "frequency");
+    assert_eq!(schema.field(1).name(), "frequency_phase");
+    assert_eq!(schema.field(2).name(), "v(1)");
+}
+
+const TRANSIENT_SAMPLE: &str = "Title: rc circuit
+Date: Sat Dec 25 20:36:08  2021
+Plotname: Transient Analysis
+Flags: real
+No. Variables: 2
+No. Points: 4
+Variables:
+\t0\ttime\ttime
+\t1\tv(out)\tvoltage
+Values:
+ 0\t0.000000000000000e+00
+\t0.000000000000000e+00
+ 1\t1.000000000000000e-03
+\t2.000000000000000e+00
+ 2\t2.000000000000000e-03
+\t6.000000000000000e+00
+ 3\t4.000000000000000e-03
+\t2.000000000000000e+00
+";
+
+#[test]
+fn interpolate_at_scale_value() {
+    let plot = parse(TRANSIENT_SAMPLE).unwrap();
+    assert_eq!(plot.interpolate("v(out)", 1e-3), Some(2.0));
+    assert_eq!(plot.interpolate("v(out)", 1.5e-3), Some(4.0));
+    assert_eq!(plot.interpolate("v(out)", 3e-3), Some(4.0));
+    assert_eq!(plot.interpolate("v(out)", 0.0), Some(0.0));
+    assert_eq!(plot.interpolate("v(out)", 5e-3), None);
+    assert_eq!(plot.interpolate("v(out)", -1e-3), None);
+    assert_eq!(plot.interpolate("v(missing)", 1e-3), None);
+}
+
+#[test]
+fn decimate_and_resample() {
+    let plot = parse(TRANSIENT_SAMPLE).unwrap();
+    let decimated = plot.decimate(2);
+    assert_eq!(decimated.no_of_points, 2);
+    assert_eq!(decimated.scale().values, vec![0.0, 2e-3]);
+    assert_eq!(decimated.data[1].values, vec![0.0, 6.0]);
+    assert_eq!(plot.decimate(0).data, plot.data);
+
+    let resampled = plot.resample_uniform(5);
+    assert_eq!(resampled.no_of_points, 5);
+    assert_eq!(resampled.scale().values, vec![0.0, 1e-3, 2e-3, 3e-3, 4e-3]);
+    assert_eq!(resampled.data[1].values, vec![0.0, 2.0, 6.0, 4.0, 2.0]);
+    assert_eq!(plot.resample_uniform(0).no_of_points, 0);
+}
+
+#[test]
+fn slice_range_is_inclusive() {
+    let plot = parse(TRANSIENT_SAMPLE).unwrap();
+    let sliced = plot.slice_range(1e-3, 2e-3);
+    assert_eq!(sliced.no_of_points, 2);
+    assert_eq!(sliced.scale().values, vec![1e-3, 2e-3]);
+    assert_eq!(sliced.data[1].values, vec![2.0, 6.0]);
+    let empty = plot.slice_range(5e-3, 6e-3);
+    assert_eq!(empty.no_of_points, 0);
+    assert!(empty.data[1].values.is_empty());
+    assert_eq!(plot.slice_range(3e-3, 1e-3).no_of_points, 0);
+}
+
+#[test]
+fn parse_with_spice_suffixes() {
+    let file = TRANSIENT_SAMPLE
+        .replace("1.000000000000000e-03", "1m")
+        .replace("6.000000000000000e+00", "6MEG")
+        .replace("4.000000000000000e-03", "4000u");
+    assert!(matches!(
+        parse(&file),
+        Result::Err(SpiceParseError::At { line: 13, .. })
+    ));
+    let opts = ParseOptions {
+        spice_suffixes: true,
+        ..ParseOptions::default()
+    };
+    let plot = parse_with(&file, opts).unwrap();
+    assert_eq!(plot.scale().values, vec![0.0, 1e-3, 2e-3, 4e-3]);
+    assert_eq!(plot.data[1].values, vec![0.0, 2.0, 6e6, 2.0]);
+    assert!(parse_with(&file.replace("6MEG", "6x"), opts).is_err());
+}
+
+#[test]
+fn magnitude_and_phase_accessors() {
+    let plot = parse(AC_ANALYSIS_SAMPLE).unwrap();
+    let var_data = &plot.data[1];
+    assert!(var_data.is_complex());
+    assert_eq!(var_data.magnitude(), var_data.values.as_slice());
+    assert_eq!(var_data.phase(), var_data.angles.as_deref());
+    let real = parse(TRANSIENT_SAMPLE).unwrap();
+    assert!(!real.data[1].is_complex());
+    assert_eq!(real.data[1].phase(), None);
+}
+
+#[test]
+fn iterate_plot_variables() {
+    let plot = parse(TRANSIENT_SAMPLE).unwrap();
+    let mut names: Vec<&str> = Vec::new();
+    for var_data in &plot {
+        names.push(&var_data.name);
+    }
+    assert_eq!(names, vec!["time", "v(out)"]);
+    let owned: Vec<String> = plot.into_iter().map(|var_data| var_data.name).collect();
+    assert_eq!(owned, vec!["time", "v(out)"]);
+}
+
+#[test]
+fn index_plot_by_name() {
+    let plot = parse(TRANSIENT_SAMPLE).unwrap();
+    assert_eq!(plot["v(out)"].values, vec![0.0, 2.0, 6.0, 2.0]);
+}
+
+#[test]
+#[should_panic(expected = "no variable named `v(missing)` in plot")]
+fn index_plot_by_missing_name() {
+    let plot = parse(TRANSIENT_SAMPLE).unwrap();
+    let _ = &plot["v(missing)"];
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn json_round_trip() {
+    let plot = parse(TRANSIENT_SAMPLE).unwrap();
+    let json = serde_json::to_string(&plot).unwrap();
+    let loaded: crate::Plot = serde_json::from_str(&json).unwrap();
+    assert_eq!(loaded, plot);
+}
+
+#[test]
+fn merge_plots_by_scale() {
+    let plot = parse(TRANSIENT_SAMPLE).unwrap();
+    let merged = crate::Plot::merge_by_scale(&[plot.clone(), plot.clone()]).unwrap();
+    assert_eq!(merged.no_of_variables, 3);
+    let names: Vec<&str> = merged.data.iter().map(|v| v.name.as_str()).collect();
+    assert_eq!(names, vec!["time", "0:v(out)", "1:v(out)"]);
+    assert_eq!(merged["1:v(out)"].values, plot["v(out)"].values);
+
+    let shorter = plot.decimate(2);
+    assert!(matches!(
+        crate::Plot::merge_by_scale(&[plot.clone(), shorter]),
+        Result::Err(SpiceParseError::ScaleMismatch)
+    ));
+    let mut shifted = plot.clone();
+    shifted.data[0].values[1] = 1.5e-3;
+    assert!(crate::Plot::merge_by_scale(&[plot, shifted]).is_err());
+    assert!(crate::Plot::merge_by_scale(&[]).is_err());
+}
+
+#[test]
+fn build_plot_and_write_rawfile() {
+    let plot = crate::PlotBuilder::new()
+        .title("synthetic")
+        .plotname("Transient Analysis")
+        .variable("time", "time", vec![0.0, 1.0, 2.0])
+        .variable("v(out)", "voltage", vec![0.5, 1.5, -2.0])
+        .build()
+        .unwrap();
+    assert_eq!(plot.no_of_points, 3);
+    assert_eq!(plot.no_of_variables, 2);
+    assert_eq!(parse(&plot.to_rawfile()).unwrap(), plot);
+
+    let complex = crate::PlotBuilder::new()
+        .flags(Flags::Complex)
+        .variable("frequency", "frequency", vec![1.0, 10.0])
+        .complex_variable("v(out)", "voltage", vec![0.0, 3.0], vec![1.0, 4.0])
+        .build()
+        .unwrap();
+    assert_eq!(complex.data[1].values, vec![1.0, 5.0]);
+    assert_eq!(complex.data[0].imag, Some(vec![0.0, 0.0]));
+
+    let mismatch = crate::PlotBuilder::new()
+        .variable("time", "time", vec![0.0, 1.0])
+        .variable("v(out)", "voltage", vec![0.0])
+        .build();
+    assert!(matches!(
+        mismatch,
+        Result::Err(SpiceParseError::NoOfPointMismatch)
+    ));
+    let not_complex = crate::PlotBuilder::new()
+        .complex_variable("v(out)", "voltage", vec![0.0], vec![1.0])
+        .build();
+    assert!(matches!(
+        not_complex,
+        Result::Err(SpiceParseError::NotComplex)
+    ));
+}
+
+#[test]
+fn variable_kind() {
+    use crate::VarKind;
+    let plot = parse(TRANSIENT_SAMPLE).unwrap();
+    assert_eq!(plot.data[0].kind(), VarKind::Time);
+    assert_eq!(plot.data[1].kind(), VarKind::Voltage);
+    assert_eq!(VarKind::from("Current"), VarKind::Current);
+    assert_eq!(VarKind::from("FREQUENCY"), VarKind::Frequency);
+    assert_eq!(
+        VarKind::from("impedance"),
+        VarKind::Other(String::from("impedance"))
+    );
+}
+
+#[test]
+fn malformed_variable_line() {
+    let file = TRANSIENT_SAMPLE.replace("\t1\tv(out)\tvoltage", "\t1\tv(out)");
+    assert!(matches!(
+        parse(&file),
+        Result::Err(SpiceParseError::MalformedVariableLine { line: 9 })
+    ));
+    assert!(matches!(
+        parse_borrowed(&file),
+        Result::Err(SpiceParseError::MalformedVariableLine { line: 9 })
+    ));
+}
+
+#[test]
+fn malformed_complex_value() {
+    let file = AC_ANALYSIS_SAMPLE.replace(
+        "1.999874869678668e+00,-1.581913351188419e-02",
+        "1.999874869678668e+00",
+    );
+    assert!(matches!(
+        parse(&file),
+        Result::Err(SpiceParseError::MalformedComplexValue { line: 20 })
+    ));
+}
+
+#[test]
+fn actual_points_and_variables() {
+    let mut plot = parse(TRANSIENT_SAMPLE).unwrap();
+    assert_eq!(plot.actual_points(), 4);
+    assert_eq!(plot.actual_variables(), 2);
+    plot.data[1].values.pop();
+    plot.no_of_variables = 3;
+    assert_eq!(plot.actual_points(), 3);
+    assert_eq!(plot.actual_variables(), 2);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn write_csv_to_writer() {
+    let plot = parse(TRANSIENT_SAMPLE).unwrap();
+    let mut out: Vec<u8> = Vec::new();
+    crate::write_csv(&plot, &mut out, CsvOptions::default()).unwrap();
+    let expected = parse_and_get_csv(TRANSIENT_SAMPLE).unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), expected);
+    assert!(expected.starts_with("time - time,v(out) - voltage\n0,0\n0.001,2\n"));
+}
+
+#[test]
+fn csv_row_per_variable() {
+    let opts = CsvOptions {
+        layout: crate::CsvLayout::RowPerVariable,
+        ..CsvOptions::default()
+    };
+    assert_eq!(
+        parse_and_get_csv_with(TRANSIENT_SAMPLE, opts).unwrap(),
+        "time - time,0,0.001,0.002,0.004\nv(out) - voltage,0,2,6,2\n"
+    );
+    let complex = parse_and_get_csv_with(AC_ANALYSIS_SAMPLE, opts).unwrap();
+    let rows: Vec<&str> = complex.lines().collect();
+    assert_eq!(rows.len(), 8);
+    assert!(rows[1].starts_with("\"frequency(phase,deg)\",0,"));
+}
+
+#[cfg(feature = "chrono")]
+#[test]
+fn plot_datetime() {
+    let mut plot = parse(TRANSIENT_SAMPLE).unwrap();
+    let datetime = chrono::NaiveDate::from_ymd_opt(2021, 12, 25)
+        .unwrap()
+        .and_hms_opt(20, 36, 8)
+        .unwrap();
+    assert_eq!(plot.datetime(), Some(datetime));
+    plot.date = String::from("Wed Feb 10 14:22:01 2021");
+    assert!(plot.datetime().is_some());
+    plot.date = String::from("yesterday");
+    assert_eq!(plot.datetime(), None);
+}
+
+#[test]
+fn date_keeps_colons() {
+    let plot = parse(TRANSIENT_SAMPLE).unwrap();
+    assert_eq!(plot.date, "Sat Dec 25 20:36:08  2021");
+    let borrowed = parse_borrowed(TRANSIENT_SAMPLE).unwrap();
+    assert_eq!(borrowed.date, "Sat Dec 25 20:36:08  2021");
+}
+
+#[test]
+fn header_values_keep_colons() {
+    let file = TRANSIENT_SAMPLE.replace(
+        "Title: rc circuit",
+        "Title: rc: step response\nCommand: source C:\\sim\\rc.cir",
+    );
+    let plot = parse(&file).unwrap();
+    assert_eq!(plot.title, "rc: step response");
+    assert_eq!(plot.command.as_deref(), Some("source C:\\sim\\rc.cir"));
+    assert_eq!(plot.date, "Sat Dec 25 20:36:08  2021");
+    let borrowed = parse_borrowed(&file).unwrap();
+    assert_eq!(borrowed.title, "rc: step response");
+}
+
+#[test]
+fn parse_reports_progress() {
+    let mut calls: Vec<(usize, usize)> = Vec::new();
+    let plot =
+        crate::parse_with_progress(TRANSIENT_SAMPLE, |done, total| calls.push((done, total)))
+            .unwrap();
+    assert_eq!(plot, parse(TRANSIENT_SAMPLE).unwrap());
+    assert_eq!(calls, vec![(4, 4)]);
+
+    let mut file = String::from(
+        "Title: long\nPlotname: Transient Analysis\nFlags: real\nNo. Variables: 1\nNo. Points: 2048\nVariables:\n\t0\ttime\ttime\nValues:\n",
+    );
+    for i in 0..2048 {
+        file += format!(" {}\t{}\n", i, i).as_str();
+    }
+    let mut calls: Vec<(usize, usize)> = Vec::new();
+    crate::parse_with_progress(&file, |done, total| calls.push((done, total))).unwrap();
+    assert_eq!(calls, vec![(1024, 2048), (2048, 2048)]);
+}
+
+#[test]
+fn parse_single_variable() {
+    let var_data = crate::parse_variable(TRANSIENT_SAMPLE, "v(out)").unwrap();
+    assert_eq!(var_data, parse(TRANSIENT_SAMPLE).unwrap()["v(out)"]);
+    let var_data = crate::parse_variable(AC_ANALYSIS_SAMPLE, "v(2)").unwrap();
+    assert_eq!(var_data, parse(AC_ANALYSIS_SAMPLE).unwrap()["v(2)"]);
+    assert!(matches!(
+        crate::parse_variable(TRANSIENT_SAMPLE, "v(missing)"),
+        Result::Err(SpiceParseError::VariableNotFound(name)) if name == "v(missing)"
+    ));
+
+    let file = binary_sample(&[100.0, 30.0, -1.0, 50.0, 15.0, -0.5]);
+    let var_data = crate::parse_variable_bytes(&file, "v(b)").unwrap();
+    assert_eq!(var_data.values, vec![30.0, 15.0]);
+    let truncated = &file[..file.len() - 8];
+    assert!(matches!(
+        crate::parse_variable_bytes(truncated, "i(v1)"),
+        Result::Err(SpiceParseError::TruncatedBinary)
+    ));
+    let var_data = crate::parse_variable_bytes(TRANSIENT_SAMPLE.as_bytes(), "time").unwrap();
+    assert_eq!(var_data.values, vec![0.0, 1e-3, 2e-3, 4e-3]);
+}
+
+#[test]
+fn variable_statistics() {
+    let plot = parse(TRANSIENT_SAMPLE).unwrap();
+    let v_out = &plot["v(out)"];
+    assert_eq!(v_out.min(), Some(0.0));
+    assert_eq!(v_out.max(), Some(6.0));
+    assert_eq!(v_out.mean(), Some(2.5));
+    assert_eq!(v_out.rms(), Some(11f64.sqrt()));
+    let empty = plot.slice_range(1.0, 2.0);
+    assert_eq!(empty["v(out)"].min(), None);
+    assert_eq!(empty["v(out)"].mean(), None);
+    assert_eq!(empty["v(out)"].rms(), None);
+}
+
+#[test]
+fn argmax_and_argmin() {
+    let plot = parse(TRANSIENT_SAMPLE).unwrap();
+    assert_eq!(plot.argmax("v(out)"), Some((2e-3, 6.0)));
+    assert_eq!(plot.argmin("v(out)"), Some((0.0, 0.0)));
+    assert_eq!(plot.argmax("v(missing)"), None);
+    assert_eq!(plot.slice_range(1.0, 2.0).argmax("v(out)"), None);
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn parse_gzip_file() {
+    use std::io::Write;
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(TRANSIENT_SAMPLE.as_bytes()).unwrap();
+    let path = std::env::temp_dir().join("ngspice-parser-parse-gzip-file.raw.gz");
+    std::fs::write(&path, encoder.finish().unwrap()).unwrap();
+    let plot = crate::parse_gz(&path).unwrap();
+    let detected = crate::parse_file(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(plot, parse(TRANSIENT_SAMPLE).unwrap());
+    assert_eq!(detected, plot);
+}
+
+#[test]
+fn points_split_by_value_count() {
+    let file = "Title: rc circuit
+Plotname: Transient Analysis
+Flags: real
+No. Variables: 2
+No. Points: 3
+Variables:
+\t0\ttime\ttime
+\t1\tv(out)\tvoltage
+Values:
+ 0\t0.000000000000000e+00
+\t1.000000000000000e+00
+1.000000000000000e-03
+\t2.000000000000000e+00
+ 2\t2.000000000000000e-03
+\t6.000000000000000e+00
+";
+    let plot = parse(file).unwrap();
+    assert_eq!(plot.scale().values, vec![0.0, 1e-3, 2e-3]);
+    assert_eq!(plot["v(out)"].values, vec![1.0, 2.0, 6.0]);
+}
+
+#[test]
+fn point_index_is_optional() {
+    let file = "Title: rc circuit frequency response
+Plotname: AC Analysis
+Flags: complex
+No. Variables: 2
+No. Points: 2
+Variables:
+\t0\tfrequency\tfrequency
+\t1\tv(out)\tvoltage
+Values:
+ 0\t1.000000000000000e+00,0.000000000000000e+00
+\t3.000000000000000e+00, 4.000000000000000e+00
+1.000000000000000e+01,0.000000000000000e+00
+\t0.000000000000000e+00,2.000000000000000e+00
+";
+    let plot = parse(file).unwrap();
+    assert_eq!(plot.scale().values, vec![1.0, 10.0]);
+    assert_eq!(plot["v(out)"].values, vec![5.0, 2.0]);
+
+    let short = TRANSIENT_SAMPLE.replace("\t2.000000000000000e+00\n 2", "\n 2");
+    assert!(matches!(
+        parse(&short),
+        Result::Err(SpiceParseError::At { line: 15, .. })
+    ));
+}
+
+#[cfg(target_pointer_width = "64")]
+#[test]
+fn largest_point_index() {
+    let file = TRANSIENT_SAMPLE
+        .replace(" 1\t1.0", " 18446744073709551615\t1.0")
+        .replace(" 3\t4.0", "4.0");
+    let max = usize::MAX;
+    let plot = parse(&file).unwrap();
+    assert_eq!(plot.point_indices, vec![0, max, 2, 3]);
+    let progress = crate::parse_with_progress(&file, |_, _| {}).unwrap();
+    assert_eq!(progress.point_indices, plot.point_indices);
+    let last = file.replace(" 2\t2.0", "2.0");
+    let plot = parse(&last).unwrap();
+    assert_eq!(plot.point_indices, vec![0, max, max, max]);
+    let mut parser = crate::PlotParser::new();
+    parser.feed(&last);
+    assert_eq!(parser.finish().unwrap().point_indices, plot.point_indices);
+}
+
+#[test]
+fn point_indices_follow_rawfile() {
+    let file = TRANSIENT_SAMPLE
+        .replace(" 1\t1.0", " 10\t1.0")
+        .replace(" 2\t2.0", " 20\t2.0")
+        .replace(" 3\t4.0", "4.0");
+    let plot = parse(&file).unwrap();
+    assert_eq!(plot.point_indices, vec![0, 10, 20, 21]);
+    assert_eq!(plot.slice_range(1e-3, 2e-3).point_indices, vec![10, 20]);
+    assert_eq!(plot.decimate(2).point_indices, vec![0, 20]);
+    assert_eq!(
+        parse_borrowed(&file).unwrap().point_indices,
+        plot.point_indices
+    );
+    let progress = crate::parse_with_progress(&file, |_, _| {}).unwrap();
+    assert_eq!(progress.point_indices, plot.point_indices);
+    let binary = parse_binary(&binary_sample(&[100.0, 30.0, -1.0, 50.0, 15.0, -0.5])).unwrap();
+    assert_eq!(binary.point_indices, vec![0, 1]);
+}
+
+#[test]
+fn fold_over_points() {
+    let integral = crate::fold_points(TRANSIENT_SAMPLE, (0.0, None), |(sum, prev), row| {
+        let (time, value) = (row[0], row[1]);
+        let sum = match prev {
+            Option::Some((t0, v0)) => sum + (time - t0) * (value + v0) / 2.0,
+            Option::None => sum,
+        };
+        (sum, Option::Some((time, value)))
+    })
+    .unwrap();
+    assert!((integral.0 - 0.013).abs() < 1e-12);
+    let short = TRANSIENT_SAMPLE.replace("No. Points: 4", "No. Points: 5");
+    assert!(matches!(
+        crate::fold_points(&short, 0, |count, _| count + 1),
+        Result::Err(SpiceParseError::NoOfPointMismatch)
+    ));
+}
+
+#[test]
+fn scale_kind_from_type() {
+    use crate::ScaleKind;
+    assert_eq!(
+        parse(TRANSIENT_SAMPLE).unwrap().scale_kind(),
+        ScaleKind::Time
+    );
+    assert_eq!(
+        parse(AC_ANALYSIS_SAMPLE).unwrap().scale_kind(),
+        ScaleKind::Frequency
+    );
+    let dc = TRANSIENT_SAMPLE.replace("\t0\ttime\ttime", "\t0\tv(in)\tvoltage");
+    assert_eq!(
+        parse(&dc).unwrap().scale_kind(),
+        ScaleKind::Sweep(String::from("voltage"))
+    );
+    let notype = TRANSIENT_SAMPLE.replace("\t0\ttime\ttime", "\t0\tindex\tnotype");
+    assert_eq!(parse(&notype).unwrap().scale_kind(), ScaleKind::Unknown);
+    assert_eq!(
+        crate::PlotBuilder::new().build().unwrap().scale_kind(),
+        ScaleKind::Unknown
+    );
+}
+
+#[test]
+fn magnitude_in_decibels() {
+    let plot = parse(AC_ANALYSIS_SAMPLE).unwrap();
+    let db = plot["v(1)"].magnitude_db().unwrap();
+    assert!((db[0] - 20.0 * 2f64.log10()).abs() < 1e-12);
+    assert_eq!(
+        parse(TRANSIENT_SAMPLE).unwrap()["v(out)"].magnitude_db(),
+        None
+    );
+    let mut zero = plot["v(1)"].clone();
+    zero.values[0] = 0.0;
+    assert_eq!(zero.magnitude_db().unwrap()[0], f64::NEG_INFINITY);
+
+    let opts = CsvOptions {
+        magnitude_db: true,
+        ..CsvOptions::default()
+    };
+    let csv = parse_and_get_csv_with(AC_ANALYSIS_SAMPLE, opts).unwrap();
+    let mut lines = csv.lines();
+    assert!(lines.next().unwrap().contains("v(1) - voltage(dB),"));
+    let cells: Vec<&str> = lines.next().unwrap().split(',').collect();
+    assert_eq!(cells[0], "0");
+    assert_eq!(cells[2], (20.0 * 2f64.log10()).to_string());
+}
+
+#[test]
+fn unwrap_phase() {
+    let theta: Vec<f64> = (0..16).map(|i| -0.9 * i as f64).collect();
+    let plot = crate::PlotBuilder::new()
+        .flags(Flags::Complex)
+        .variable(
+            "frequency",
+            "frequency",
+            (0..16).map(|i| i as f64).collect(),
+        )
+        .complex_variable(
+            "v(out)",
+            "voltage",
+            theta.iter().map(|t| t.cos()).collect(),
+            theta.iter().map(|t| t.sin()).collect(),
+        )
+        .build()
+        .unwrap();
+    let wrapped = plot["v(out)"].phase().unwrap();
+    assert!(wrapped.iter().all(|p| p.abs() <= std::f64::consts::PI));
+    let unwrapped = plot["v(out)"].phase_unwrapped().unwrap();
+    for (got, want) in unwrapped.iter().zip(theta.iter()) {
+        assert!((got - want).abs() < 1e-9);
+    }
+    assert_eq!(
+        parse(TRANSIENT_SAMPLE).unwrap()["v(out)"].phase_unwrapped(),
+        None
+    );
+}
+
+#[test]
+fn group_delay_of_pure_delay() {
+    let delay = 1e-3;
+    let freq: Vec<f64> = (0..20).map(|i| 100.0 * i as f64).collect();
+    let theta: Vec<f64> = freq
+        .iter()
+        .map(|f| -2.0 * std::f64::consts::PI * f * delay)
+        .collect();
+    let plot = crate::PlotBuilder::new()
+        .flags(Flags::Complex)
+        .variable("frequency", "frequency", freq.clone())
+        .complex_variable(
+            "v(out)",
+            "voltage",
+            theta.iter().map(|t| t.cos()).collect(),
+            theta.iter().map(|t| t.sin()).collect(),
+        )
+        .build()
+        .unwrap();
+    let group_delay = plot.group_delay("v(out)").unwrap();
+    assert_eq!(group_delay.len(), freq.len());
+    assert!(group_delay.iter().all(|d| (d - delay).abs() < 1e-9));
+    assert_eq!(plot.group_delay("v(missing)"), None);
+    assert_eq!(parse(TRANSIENT_SAMPLE).unwrap().group_delay("v(out)"), None);
+}
+
+#[test]
+fn validate_column_lengths() {
+    let mut plot = parse(AC_ANALYSIS_SAMPLE).unwrap();
+    assert!(plot.validate().is_ok());
+    plot.data[2].angles.as_mut().unwrap().pop();
+    assert!(matches!(
+        plot.validate(),
+        Result::Err(SpiceParseError::ColumnLengthMismatch(name)) if name == "v(2)"
+    ));
+    let mut plot = parse(TRANSIENT_SAMPLE).unwrap();
+    plot.data[1].values.push(1.0);
+    assert!(plot.validate().is_err());
+}
+
+#[test]
+fn parse_incrementally() {
+    let mut parser = crate::PlotParser::new();
+    let bytes = AC_ANALYSIS_SAMPLE.as_bytes();
+    let half = AC_ANALYSIS_SAMPLE.len() / 2;
+    for chunk in bytes[..half].chunks(7) {
+        parser.feed(std::str::from_utf8(chunk).unwrap());
+    }
+    let partial = parser.plot().unwrap();
+    assert!(partial.actual_points() > 0 && partial.actual_points() < 10);
+    for chunk in bytes[half..].chunks(7) {
+        parser.feed(std::str::from_utf8(chunk).unwrap());
+    }
+    assert_eq!(parser.finish().unwrap(), parse(AC_ANALYSIS_SAMPLE).unwrap());
+
+    let mut parser = crate::PlotParser::new();
+    parser.feed(TRANSIENT_SAMPLE.trim_end());
+    assert_eq!(parser.finish().unwrap(), parse(TRANSIENT_SAMPLE).unwrap());
+
+    let mut parser = crate::PlotParser::new();
+    parser.feed(&TRANSIENT_SAMPLE[..TRANSIENT_SAMPLE.len() - 24]);
+    assert!(parser.finish().is_err());
+}
+
+#[test]
+fn parse_with_warnings() {
+    let file = TRANSIENT_SAMPLE.replace(
+        "Flags: real",
+        "Flags: real padded\nOption: reltol=1e-4\nstray text",
+    );
+    let (plot, warnings) = crate::parse_verbose(&file).unwrap();
+    assert_eq!(
+        plot.extra_headers,
+        vec![(String::from("Option"), String::from("reltol=1e-4"))]
+    );
+    let warnings: Vec<(usize, &str)> = warnings
+        .iter()
+        .map(|warning| (warning.line, warning.message.as_str()))
+        .collect();
+    assert_eq!(
+        warnings,
+        vec![
+            (4, "Unrecognized flag `padded`"),
+            (5, "Unknown header `Option`"),
+            (6, "Ignored line without a header key"),
+        ]
+    );
+    assert!(crate::parse_verbose(TRANSIENT_SAMPLE).unwrap().1.is_empty());
+}
+
+#[test]
+fn lenient_parse_of_truncated_plot() {
+    let file = TRANSIENT_SAMPLE.replace(
+        "3\t4.000000000000000e-03\n\t2.000000000000000e+00\n",
+        "3\t4.000000000000000e-03\n",
+    );
+    assert!(parse(&file).is_err());
+    let opts = ParseOptions {
+        lenient: true,
+        ..ParseOptions::default()
+    };
+    let (plot, warnings) = crate::parse_verbose_with(&file, opts).unwrap();
+    assert_eq!(plot.no_of_points, 3);
+    assert_eq!(plot.point_indices, vec![0, 1, 2]);
+    assert_eq!(plot.scale().values, vec![0.0, 1e-3, 2e-3]);
+    assert_eq!(plot.data[1].values, vec![0.0, 2.0, 6.0]);
+    let messages: Vec<&str> = warnings
+        .iter()
+        .map(|warning| warning.message.as_str())
+        .collect();
+    assert_eq!(
+        messages,
+        vec![
+            "Dropped a point with the wrong number of values",
+            "Expected 4 points but read 3"
+        ]
+    );
+    assert_eq!(parse_with(&file, opts).unwrap(), plot);
+    let whole = parse_with(TRANSIENT_SAMPLE, opts).unwrap();
+    assert_eq!(whole, parse(TRANSIENT_SAMPLE).unwrap());
+}
+
+#[test]
+fn lenient_parse_resyncs_after_a_gap() {
+    let opts = ParseOptions {
+        lenient: true,
+        ..ParseOptions::default()
+    };
+    let gap = TRANSIENT_SAMPLE.replace(
+        " 1\t1.000000000000000e-03\n\t2.000000000000000e+00\n",
+        " 1\t1.000000000000000e-03\n",
+    );
+    let extra = TRANSIENT_SAMPLE.replace(" 1\t1.000000000000000e-03", " 1\t1\t5");
+    for (file, warning_lines) in [(gap, [14, 17]), (extra, [13, 18])] {
+        assert!(parse(&file).is_err());
+        let (plot, warnings) = crate::parse_verbose_with(&file, opts).unwrap();
+        assert_eq!(plot.no_of_points, 3);
+        assert_eq!(plot.point_indices, vec![0, 2, 3]);
+        assert_eq!(plot.scale().values, vec![0.0, 2e-3, 4e-3]);
+        assert_eq!(plot.data[1].values, vec![0.0, 6.0, 2.0]);
+        let lines: Vec<usize> = warnings.iter().map(|warning| warning.line).collect();
+        assert_eq!(lines, warning_lines);
+        assert_eq!(warnings[1].message, "Expected 4 points but read 3");
+    }
+}
+
+#[test]
+fn units_from_variable_types() {
+    let plot = parse(TRANSIENT_SAMPLE).unwrap();
+    assert_eq!(plot.data[0].unit(), Some("s"));
+    assert_eq!(plot.data[1].unit(), Some("V"));
+    let mut var_data = plot.data[1].clone();
+    var_data.typee = String::from("Current");
+    assert_eq!(var_data.unit(), Some("A"));
+    var_data.typee = String::from("notype");
+    assert_eq!(var_data.unit(), None);
+    assert_eq!(var_data.typee, "notype");
+}
+
+#[test]
+fn select_custom_scale() {
+    let plot = parse(TRANSIENT_SAMPLE).unwrap();
+    let by_voltage = plot.with_scale("v(out)").unwrap();
+    assert_eq!(by_voltage.scale().name, "v(out)");
+    assert_eq!(by_voltage.data[1].name, "time");
+    assert_eq!(by_voltage.argmax("time"), Some((2.0, 4e-3)));
+    assert_eq!(plot.with_scale("time").unwrap(), plot);
+    assert!(matches!(
+        plot.with_scale("v(in)"),
+        Result::Err(SpiceParseError::VariableNotFound(name)) if name == "v(in)"
+    ));
+}
+
+#[test]
+fn csv_complex_formats() {
+    let file = "Title: rc circuit
+Plotname: AC Analysis
+Flags: complex
+No. Variables: 1
+No. Points: 1
+Variables:
+\t0\tv(1)\tvoltage
+Values:
+ 0\t3.0,4.0
+";
+    let csv = |complex_format| {
+        parse_and_get_csv_with(
+            file,
+            CsvOptions {
+                delimiter: ';',
+                complex_format,
+                ..CsvOptions::default()
+            },
+        )
+        .unwrap()
+    };
+    let phase = 4.0f64.atan2(3.0).to_degrees();
+    assert_eq!(
+        csv(crate::ComplexFormat::Polar),
+        format!("v(1) - voltage;voltage(phase,deg)\n5;{}\n", phase)
+    );
+    assert_eq!(
+        csv(crate::ComplexFormat::Rectangular),
+        "v(1) - voltage(real);voltage(imag)\n3;4\n"
+    );
+    assert_eq!(
+        csv(crate::ComplexFormat::Both),
+        format!(
+            "v(1) - voltage;voltage(phase,deg);v(1) - voltage(real);voltage(imag)\n5;{};3;4\n",
+            phase
+        )
+    );
+}
+
+const SPACED_NAME_SAMPLE: &str = "Title: rc circuit
+Date: Sat Dec 25 20:36:08  2021
+Plotname: Transient Analysis
+Flags: real
+No. Variables: 2
+No. Points: 1
+Variables:
+\t0\ttime\ttime
+\t1\tv(x1.out node)\tvoltage
+Values:
+ 0\t0.000000000000000e+00
+\t1.000000000000000e+00
+";
+
+#[test]
+fn variable_name_with_spaces() {
+    let plot = parse(SPACED_NAME_SAMPLE).unwrap();
+    assert_eq!(plot.data[1].name, "v(x1.out node)");
+    assert_eq!(plot.data[1].typee, "voltage");
+    assert_eq!(plot["v(x1.out node)"].values, vec![1.0]);
+    let borrowed = parse_borrowed(SPACED_NAME_SAMPLE).unwrap();
+    assert_eq!(borrowed.data[1].name, "v(x1.out node)");
+    let spaced = SPACED_NAME_SAMPLE.replace("\t0\ttime\ttime", " 0 time time");
+    assert_eq!(parse(&spaced).unwrap().data[0].name, "time");
+    let gnuplot = parse_and_get_gnuplot(SPACED_NAME_SAMPLE).unwrap();
+    assert_eq!(gnuplot.lines().next(), Some("# time \"v(x1.out node)\""));
+    let quoted = SPACED_NAME_SAMPLE.replace("v(x1.out node)", "v(\"a b\")");
+    let gnuplot = parse_and_get_gnuplot(&quoted).unwrap();
+    assert_eq!(gnuplot.lines().next(), Some("# time \"v(\\\"a b\\\")\""));
+}
+
+#[test]
+fn variable_extra_columns() {
+    let plot = parse(AC_ANALYSIS_SAMPLE).unwrap();
+    assert_eq!(plot.data[0].typee, "frequency");
+    assert_eq!(plot.data[0].extra, vec![String::from("grid=3")]);
+    assert!(plot.data[1].extra.is_empty());
+    assert_eq!(
+        parse_borrowed(AC_ANALYSIS_SAMPLE).unwrap().data[0].extra,
+        vec!["grid=3"]
+    );
+    assert_eq!(
+        parse(&plot.to_rawfile()).unwrap().data[0].extra,
+        plot.data[0].extra
+    );
+    let file =
+        TRANSIENT_SAMPLE.replace("\t1\tv(out)\tvoltage", "\t1\tv(out)\tvoltage\tmin=0 max=6");
+    assert_eq!(parse(&file).unwrap().data[1].extra, vec!["min=0", "max=6"]);
+}
+
+#[test]
+fn flags_from_str() {
+    assert_eq!("real".parse::<Flags>().unwrap(), Flags::Real);
+    assert_eq!("complex".parse::<Flags>().unwrap(), Flags::Complex);
+    assert_eq!("COMPLEX".parse::<Flags>().unwrap(), Flags::Complex);
+    assert_eq!("Real".parse::<Flags>().unwrap(), Flags::Real);
+    assert!(matches!(
+        "padded".parse::<Flags>(),
+        Result::Err(SpiceParseError::UnknownFlag)
+    ));
+    assert!("".parse::<Flags>().is_err());
+}
+
+#[test]
+fn display_plot_summary() {
+    let plot = parse(TRANSIENT_SAMPLE).unwrap();
+    assert_eq!(
+        plot.to_string(),
+        "Title: rc circuit
+Plotname: Transient Analysis
+Flags: real
+No. Variables: 2
+No. Points: 4
+Variables:
+\t0\ttime\ttime
+\t1\tv(out)\tvoltage
+"
+    );
+    assert!(parse(AC_ANALYSIS_SAMPLE)
+        .unwrap()
+        .to_string()
+        .contains("Flags: complex\n"));
+}
+
+#[test]
+fn plot_len_and_points() {
+    let plot = parse(TRANSIENT_SAMPLE).unwrap();
+    assert_eq!(plot.len(), 2);
+    assert!(!plot.is_empty());
+    assert_eq!(plot.points(), 4);
+    assert_eq!(plot.len(), plot.into_iter().count());
+    let empty = crate::PlotBuilder::new().build().unwrap();
+    assert!(empty.is_empty());
+    assert_eq!(empty.points(), 0);
+}
+
+#[test]
+fn short_variable_table() {
+    let file = "Title: rc circuit
+Plotname: Transient Analysis
+Flags: real
+No. Variables: 3
+No. Points: 1
+Variables:
+\t0\ttime\ttime
+\t1\tv(out)\tvoltage
+Values:
+ 0\t0.000000000000000e+00
+\t1.000000000000000e+00
+\t2.000000000000000e+00
+";
+    assert!(matches!(
+        parse(file),
+        Result::Err(SpiceParseError::At { line: 9, source }) if matches!(*source, SpiceParseError::NoOfVarMismatch)
+    ));
+    assert!(parse_borrowed(file).is_err());
+    let no_table = "Title: rc circuit
+Flags: real
+No. Variables: 1
+No. Points: 1
+Values:
+ 0\t0.000000000000000e+00
+";
+    assert!(matches!(
+        parse(no_table),
+        Result::Err(SpiceParseError::At { line: 5, source }) if matches!(*source, SpiceParseError::NoOfVarMismatch)
+    ));
+}
+
+#[test]
+fn first_plotname_wins() {
+    let file = TRANSIENT_SAMPLE.replace("Flags: real", "Plotname: Operating Point\nFlags: real");
+    let (plot, warnings) = crate::parse_verbose(&file).unwrap();
+    assert_eq!(plot.plotname, "Transient Analysis");
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].line, 4);
+    assert_eq!(
+        warnings[0].message,
+        "Repeated Plotname `Operating Point` ignored"
+    );
+    assert_eq!(
+        parse_borrowed(&file).unwrap().plotname,
+        "Transient Analysis"
+    );
+}
+
+#[test]
+fn columnar_plot_layout() {
+    let columnar = parse(TRANSIENT_SAMPLE).unwrap().into_columnar();
+    assert_eq!(columnar.points, 4);
+    assert_eq!(columnar.variables[1].name, "v(out)");
+    assert_eq!(
+        columnar.values,
+        vec![0.0, 1e-3, 2e-3, 4e-3, 0.0, 2.0, 6.0, 2.0]
+    );
+    assert_eq!(columnar.column(1), Some([0.0, 2.0, 6.0, 2.0].as_slice()));
+    assert_eq!(columnar.column(2), None);
+    assert_eq!(columnar.row(2), Some(vec![2e-3, 6.0]));
+    assert_eq!(columnar.row(4), None);
+    assert_eq!(columnar.get(3, 1), Some(2.0));
+    assert_eq!(columnar.angles, None);
+    let plot = parse(AC_ANALYSIS_SAMPLE).unwrap();
+    let columnar = plot.clone().into_columnar();
+    assert_eq!(columnar.column(2).unwrap(), plot.data[2].values.as_slice());
+    assert_eq!(
+        columnar.angle_column(2).unwrap(),
+        plot.data[2].angles.as_deref().unwrap()
+    );
+}
+
+#[test]
+fn raw_slice_accessors() {
+    let plot = parse(AC_ANALYSIS_SAMPLE).unwrap();
+    let (ptr, len) = plot.data[1].values_raw();
+    assert_eq!(len, 10);
+    assert_eq!(
+        unsafe { core::slice::from_raw_parts(ptr, len) },
+        plot.data[1].values.as_slice()
+    );
+    let (ptr, len) = plot.data[1].angles_raw().unwrap();
+    assert_eq!(
+        unsafe { core::slice::from_raw_parts(ptr, len) },
+        plot.data[1].angles.as_deref().unwrap()
+    );
+    assert_eq!(parse(TRANSIENT_SAMPLE).unwrap().data[1].angles_raw(), None);
+    let columnar = plot.into_columnar();
+    let (ptr, len) = columnar.values_raw();
+    assert_eq!(len, 40);
+    assert_eq!(unsafe { *ptr.add(10) }, columnar.values[10]);
+    assert_eq!(columnar.angles_raw().map(|(_, len)| len), Some(40));
+}
+
+#[test]
+fn complex_scale_uses_real_part() {
+    let file = "Title: rc circuit
+Plotname: AC Analysis
+Flags: complex
+No. Variables: 2
+No. Points: 2
+Variables:
+\t0\tfrequency\tfrequency
+\t1\tv(1)\tvoltage
+Values:
+ 0\t1000,0
+\t0,-1
+ 1\t-2000,0
+\t0,-1
+";
+    let plot = parse(file).unwrap();
+    assert_eq!(plot.scale().values, vec![1000.0, -2000.0]);
+    assert_eq!(plot.scale().angles, Some(vec![0.0, 0.0]));
+    assert_eq!(plot.scale().real, Some(vec![1000.0, -2000.0]));
+    assert_eq!(plot.data[1].values, vec![1.0, 1.0]);
+    assert_eq!(
+        plot.data[1].angles,
+        Some(vec![-core::f64::consts::FRAC_PI_2; 2])
+    );
+    assert_eq!(
+        crate::parse_variable(file, "frequency").unwrap(),
+        plot.data[0]
+    );
+    assert_eq!(crate::parse_variable(file, "v(1)").unwrap(), plot.data[1]);
+}
+
+#[test]
+fn rectangular_complex_storage() {
+    let opts = ParseOptions {
+        complex_as: crate::ComplexStorage::Rectangular,
+        ..ParseOptions::default()
+    };
+    let polar = parse(AC_ANALYSIS_SAMPLE).unwrap();
+    let plot = parse_with(AC_ANALYSIS_SAMPLE, opts).unwrap();
+    for (var_data, polar) in plot.data.iter().zip(polar.data.iter()) {
+        assert_eq!(var_data.angles, None);
+        assert_eq!(var_data.phase(), None);
+        assert_eq!(var_data.real, polar.real);
+        assert_eq!(var_data.imag, polar.imag);
+        assert!(var_data.is_complex());
+        assert_eq!(var_data.magnitude(), polar.magnitude());
+        assert_eq!(var_data.magnitude_db(), polar.magnitude_db());
+        assert_eq!(var_data.phase_unwrapped(), polar.phase_unwrapped());
+        assert_eq!((var_data.max(), var_data.rms()), (polar.max(), polar.rms()));
+    }
+    assert_eq!(plot.to_rawfile(), polar.to_rawfile());
+    assert_eq!(plot.group_delay("v(2)"), polar.group_delay("v(2)"));
+    assert_eq!(plot.to_magnitude_plot(), polar.to_magnitude_plot());
+    assert_eq!(
+        plot.clone().into_columnar().angles,
+        polar.clone().into_columnar().angles
+    );
+    #[cfg(feature = "arrow")]
+    assert_eq!(
+        plot.to_record_batch().unwrap(),
+        polar.to_record_batch().unwrap()
+    );
+    let two_port = parse_with(TWO_PORT_SAMPLE, opts).unwrap();
+    assert_eq!(
+        two_port.to_touchstone(2).unwrap(),
+        parse(TWO_PORT_SAMPLE).unwrap().to_touchstone(2).unwrap()
+    );
+    #[cfg(feature = "std")]
+    {
+        let csv = |plot: &crate::Plot| {
+            let mut out: Vec<u8> = Vec::new();
+            crate::write_csv(plot, &mut out, CsvOptions::default()).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+        assert_eq!(csv(&plot), csv(&polar));
+    }
+}
+
+#[test]
+fn magnitude_only_plot() {
+    let plot = parse(AC_ANALYSIS_SAMPLE).unwrap();
+    let magnitudes = plot.to_magnitude_plot();
+    assert_eq!(magnitudes.flags, Flags::Real);
+    assert_eq!(magnitudes.no_of_points, plot.no_of_points);
+    for (var_data, complex) in magnitudes.data.iter().zip(plot.data.iter()) {
+        assert_eq!(var_data.name, complex.name);
+        assert_eq!(var_data.typee, complex.typee);
+        assert_eq!(var_data.values, complex.values);
+        assert_eq!(
+            (&var_data.angles, &var_data.real, &var_data.imag),
+            (&None, &None, &None)
+        );
+    }
+    assert!(magnitudes.to_rawfile().contains("Flags: real\n"));
+    let opts = ParseOptions {
+        complex_as: crate::ComplexStorage::Rectangular,
+        ..ParseOptions::default()
+    };
+    let rectangular = parse_with(AC_ANALYSIS_SAMPLE, opts).unwrap();
+    assert_eq!(rectangular.to_magnitude_plot(), magnitudes);
+}
+
+#[test]
+fn minmax_decimation_keeps_spikes() {
+    let time: Vec<f64> = (0..100).map(|i| i as f64).collect();
+    let mut spike = vec![0.0; 100];
+    spike[37] = 5.0;
+    spike[81] = -3.0;
+    let plot = crate::PlotBuilder::new()
+        .variable("time", "time", time)
+        .variable("v(out)", "voltage", spike)
+        .build()
+        .unwrap();
+    let small = plot.decimate_minmax(10);
+    assert!(small.no_of_points <= 10);
+    assert_eq!(small.argmax("v(out)"), Some((37.0, 5.0)));
+    assert_eq!(small.argmin("v(out)"), Some((81.0, -3.0)));
+    assert!(small
+        .scale()
+        .values
+        .windows(2)
+        .all(|pair| pair[0] < pair[1]));
+    assert_eq!(
+        small.point_indices,
+        small
+            .scale()
+            .values
+            .iter()
+            .map(|&t| t as usize)
+            .collect::<Vec<usize>>()
+    );
+    assert_eq!(plot.decimate_minmax(200), plot);
+}
+
+#[test]
+fn missing_header_fields() {
+    assert!(matches!(
+        parse(""),
+        Result::Err(SpiceParseError::MissingHeader("Flags"))
+    ));
+    assert!(matches!(
+        parse("just some text\n"),
+        Result::Err(SpiceParseError::MissingHeader(_))
+    ));
+    let truncated: String = TRANSIENT_SAMPLE
+        .lines()
+        .take(5)
+        .collect::<Vec<&str>>()
+        .join("\n");
+    assert!(matches!(
+        parse(&truncated),
+        Result::Err(SpiceParseError::MissingHeader("No. Points"))
+    ));
+    let no_values: String = TRANSIENT_SAMPLE
+        .lines()
+        .take(9)
+        .collect::<Vec<&str>>()
+        .join("\n");
+    assert!(matches!(
+        parse(&no_values),
+        Result::Err(SpiceParseError::MissingHeader("Values"))
+    ));
+    assert!(matches!(
+        parse_borrowed(""),
+        Result::Err(SpiceParseError::MissingHeader(_))
+    ));
+}
+
+#[test]
+fn checked_var_data_constructors() {
+    let real = crate::VarData::new_real("v(out)", "voltage", vec![1.0, 2.0]);
+    assert_eq!(real.values, vec![1.0, 2.0]);
+    assert!(!real.is_complex());
+    assert_eq!((real.real, real.imag), (None, None));
+    let complex =
+        crate::VarData::new_complex("v(1)", "voltage", vec![3.0, 0.0], vec![4.0, -2.0]).unwrap();
+    assert_eq!(complex.values, vec![5.0, 2.0]);
+    assert_eq!(
+        complex.angles,
+        Some(vec![4.0f64.atan2(3.0), -core::f64::consts::FRAC_PI_2])
+    );
+    assert_eq!(complex.real, Some(vec![3.0, 0.0]));
+    assert_eq!(complex.imag, Some(vec![4.0, -2.0]));
+    assert!(matches!(
+        crate::VarData::new_complex("v(1)", "voltage", vec![1.0], vec![]),
+        Result::Err(SpiceParseError::ColumnLengthMismatch(name)) if name == "v(1)"
+    ));
+}
+
+#[test]
+fn transfer_function_ratio() {
+    let plot = crate::PlotBuilder::new()
+        .flags(Flags::Complex)
+        .variable("frequency", "frequency", vec![1.0, 2.0, 3.0])
+        .complex_variable("v(in)", "voltage", vec![2.0, 0.0, 0.0], vec![0.0, 1.0, 0.0])
+        .complex_variable(
+            "v(out)",
+            "voltage",
+            vec![1.0, 1.0, 1.0],
+            vec![1.0, 0.0, 0.0],
+        )
+        .build()
+        .unwrap();
+    let h = plot.ratio("v(out)", "v(in)").unwrap();
+    assert_eq!(h.name, "v(out)/v(in)");
+    assert_eq!(h.typee, "notype");
+    let (real, imag) = (h.real.unwrap(), h.imag.unwrap());
+    assert_eq!((real[0], imag[0]), (0.5, 0.5));
+    assert_eq!((real[1], imag[1]), (0.0, -1.0));
+    assert!(real[2].is_nan() && imag[2].is_nan());
+    assert_eq!(h.values[1], 1.0);
+    assert!(plot.ratio("v(out)", "v(missing)").is_none());
+    let real_plot = parse(TRANSIENT_SAMPLE).unwrap();
+    let ratio = real_plot.ratio("v(out)", "time").unwrap();
+    assert!(ratio.values[0].is_nan());
+    assert_eq!(ratio.values[1], 2000.0);
+    assert!(!ratio.is_complex());
+}
+
+#[test]
+fn binary_rawfile_round_trip() {
+    for sample in [TRANSIENT_SAMPLE, AC_ANALYSIS_SAMPLE] {
+        let plot = parse(sample).unwrap();
+        let bytes = plot.to_binary_rawfile();
+        assert!(bytes.windows(8).any(|window| window == b"Binary:\n"));
+        assert_eq!(parse_bytes(&bytes).unwrap(), plot);
+        assert_eq!(parse_binary(&bytes).unwrap(), plot);
+    }
+    let plot = parse(TRANSIENT_SAMPLE).unwrap();
+    let bytes = plot.to_binary_rawfile();
+    let payload = &bytes[bytes.len() - 8 * 8..];
+    assert_eq!(payload[8 * 3..8 * 4], 2.0f64.to_le_bytes());
+    let mut short = plot.clone();
+    short.data[0].values.pop();
+    let reparsed = parse_binary(&short.to_binary_rawfile()).unwrap();
+    assert_eq!(reparsed.no_of_points, plot.no_of_points - 1);
+    assert_eq!(
+        reparsed.data[1].values,
+        plot.data[1].values[..reparsed.no_of_points]
+    );
+}
+
+#[test]
+fn duplicate_variable_names() {
+    let file = "Title: rc circuit
+Plotname: Transient Analysis
+Flags: real
+No. Variables: 3
+No. Points: 2
+Variables:
+\t0\ttime\ttime
+\t1\tv(out)\tvoltage
+\t2\tv(out)\tvoltage
+Values:
+ 0\t0.000000000000000e+00
+\t1.000000000000000e+00
+\t-1.000000000000000e+00
+ 1\t1.000000000000000e-03
+\t2.000000000000000e+00
+\t-1.000000000000000e+00
+";
+    let plot = parse(file).unwrap();
+    let matches = plot.variables_named("v(out)");
+    assert_eq!(matches.len(), 2);
+    assert_eq!(matches[0].values, vec![1.0, 2.0]);
+    assert_eq!(matches[1].values, vec![-1.0, -1.0]);
+    assert_eq!(plot.variable("v(out)").unwrap(), matches[0]);
+    assert!(plot.variables_named("v(in)").is_empty());
+}
+
+#[test]
+fn nearest_sample_lookup() {
+    let plot = parse(TRANSIENT_SAMPLE).unwrap();
+    assert_eq!(plot.sample_at("v(out)", 0.0), Some((0.0, 0.0)));
+    assert_eq!(plot.sample_at("v(out)", 1.4e-3), Some((1e-3, 2.0)));
+    assert_eq!(plot.sample_at("v(out)", 1.6e-3), Some((2e-3, 6.0)));
+    assert_eq!(plot.sample_at("v(out)", 3e-3), Some((2e-3, 6.0)));
+    assert_eq!(plot.sample_at("v(out)", -1.0), Some((0.0, 0.0)));
+    assert_eq!(plot.sample_at("v(out)", 1.0), Some((4e-3, 2.0)));
+    assert_eq!(plot.sample_at("v(out)", f64::NAN), None);
+    assert_eq!(plot.sample_at("v(in)", 0.0), None);
+}
+
+#[test]
+fn non_finite_values() {
+    let file = "Title: diverging
+Plotname: AC Analysis
+Flags: complex
+No. Variables: 2
+No. Points: 4
+Variables:
+\t0\tfrequency\tfrequency
+\t1\tv(1)\tvoltage
+Values:
+ 0\t1.0,0.0
+\tinf,0
+ 1\t2.0,0.0
+\t-inf,0
+ 2\t3.0,0.0
+\tnan,0
+ 3\t4.0,0.0
+\tinf,inf
+";
+    let plot = parse(file).unwrap();
+    let var = &plot.data[1];
+    assert_eq!(var.values[..2], [f64::INFINITY, f64::INFINITY]);
+    let angles = var.angles.as_ref().unwrap();
+    assert_eq!(angles[0], 0.0);
+    assert_eq!(angles[1], core::f64::consts::PI);
+    assert!(var.values[2].is_nan() && angles[2].is_nan());
+    assert_eq!(
+        (var.values[3], angles[3]),
+        (f64::INFINITY, core::f64::consts::FRAC_PI_4)
+    );
+    let csv = parse_and_get_csv(file).unwrap();
+    let rows: Vec<&str> = csv.lines().collect();
+    assert_eq!(rows[1], "1,0,inf,0");
+    assert_eq!(rows[2], "2,0,inf,180");
+    assert_eq!(rows[3], "3,0,NaN,NaN");
+    assert!(csv.is_ascii());
+    let real = TRANSIENT_SAMPLE
+        .replace("\t6.000000000000000e+00", "\t-inf")
+        .replace("\t2.000000000000000e+00\n 2", "\tNaN\n 2");
+    let plot = parse(&real).unwrap();
+    assert!(plot.data[1].values[1].is_nan());
+    assert_eq!(plot.data[1].values[2], f64::NEG_INFINITY);
+    let reparsed = parse(&plot.to_rawfile()).unwrap();
+    assert!(reparsed.data[1].values[1].is_nan());
+    assert_eq!(reparsed.data[1].values[2], f64::NEG_INFINITY);
+    let opts = ParseOptions {
+        spice_suffixes: true,
+        ..ParseOptions::default()
+    };
+    assert!(parse_with(&real, opts).unwrap().data[1].values[1].is_nan());
+}
+
+#[test]
+fn interned_parse_shares_strings_across_plots() {
+    let mut interner = crate::Interner::new();
+    let opts = ParseOptions::default();
+    let first = crate::parse_with_interner(TRANSIENT_SAMPLE, opts, &mut interner).unwrap();
+    let distinct = interner.len();
+    let redated = TRANSIENT_SAMPLE
+        .replace("Sat Dec 25 20:36:08  2021", "Sun Dec 26 09:00:00  2021")
+        .replace("Title: rc circuit", "Title: rc circuit, second run");
+    let second = crate::parse_with_interner(&redated, opts, &mut interner).unwrap();
+    assert_eq!(interner.len(), distinct);
+    assert!(alloc::sync::Arc::ptr_eq(&first.plotname, &second.plotname));
+    assert!(alloc::sync::Arc::ptr_eq(
+        &first.data[1].typee,
+        &second.data[1].typee
+    ));
+    assert_eq!(second.date, "Sun Dec 26 09:00:00  2021");
+    assert_eq!(crate::Plot::from(first), parse(TRANSIENT_SAMPLE).unwrap());
+    let rectangular = ParseOptions {
+        complex_as: crate::ComplexStorage::Rectangular,
+        ..ParseOptions::default()
+    };
+    let interned = crate::parse_with_interner(AC_ANALYSIS_SAMPLE, rectangular, &mut interner);
+    assert_eq!(
+        crate::Plot::from(interned.unwrap()),
+        parse_with(AC_ANALYSIS_SAMPLE, rectangular).unwrap()
+    );
+    let truncated = TRANSIENT_SAMPLE
+        .strip_suffix("\t2.000000000000000e+00\n")
+        .unwrap();
+    let lenient = ParseOptions {
+        lenient: true,
+        ..ParseOptions::default()
+    };
+    assert!(crate::parse_with_interner(truncated, opts, &mut interner).is_err());
+    let interned = crate::parse_with_interner(truncated, lenient, &mut interner).unwrap();
+    assert_eq!(interned.no_of_points, 3);
+}